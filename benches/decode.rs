@@ -0,0 +1,36 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use lightnode::vote::decode_transaction;
+use solana_transaction_status::UiConfirmedBlock;
+
+use common::*;
+
+// the fixture's txs over and over, about as many as a mainnet block has
+fn full_block(encoding: &str) -> UiConfirmedBlock { 
+    let mut block = fixture_block_with_encoding(encoding);
+    let transactions = block.transactions.take().unwrap();
+    block.transactions = Some(transactions.iter().cycle().take(transactions.len() * 250).cloned().collect());
+    block
+}
+
+fn decode_block(block: &UiConfirmedBlock) -> usize { 
+    block.transactions.iter()
+        .flatten()
+        .map(|tx| decode_transaction(&tx.transaction).unwrap())
+        .count()
+}
+
+fn bench_decode(c: &mut Criterion) { 
+    let mut group = c.benchmark_group("decode");
+    for encoding in FIXTURE_ENCODINGS { 
+        let block = full_block(encoding);
+        assert_eq!(decode_block(&block), 1250);
+        group.bench_function(encoding, |b| b.iter(|| decode_block(&block)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use lightnode::entries::{next_hash_with_tx_hash, verify_entries, verify_entries_parallel};
+use solana_sdk::{hash::{Hash, hashv}, signature::Signature};
+use solana_transaction_status::{EntryProof, PartialEntry};
+
+// a mainnet slot: 64 ticks of 12500 hashes, with a tx entry every 250 hashes
+const TICKS_PER_SLOT: u64 = 64;
+const HASHES_PER_TICK: u64 = 12_500;
+const HASHES_PER_TX_ENTRY: u64 = 250;
+
+fn slot_entries() -> (Hash, Vec<EntryProof>) { 
+    let start_blockhash = hashv(&[b"poh bench"]);
+    let mut hash = start_blockhash;
+    let mut entries = vec![];
+    for tick in 0..TICKS_PER_SLOT { 
+        let num_tx_entries = HASHES_PER_TICK / HASHES_PER_TX_ENTRY - 1;
+        for i in 0..num_tx_entries { 
+            let transaction_hash = Some(hashv(&[&tick.to_le_bytes(), &i.to_le_bytes()]));
+            hash = next_hash_with_tx_hash(&hash, HASHES_PER_TX_ENTRY, transaction_hash);
+            entries.push(EntryProof::PartialEntry(PartialEntry { num_hashes: HASHES_PER_TX_ENTRY, hash, transaction_hash }));
+        }
+        let num_hashes = HASHES_PER_TICK - num_tx_entries * HASHES_PER_TX_ENTRY;
+        hash = next_hash_with_tx_hash(&hash, num_hashes, None);
+        entries.push(EntryProof::PartialEntry(PartialEntry { num_hashes, hash, transaction_hash: None }));
+    }
+    (start_blockhash, entries)
+}
+
+fn bench_verify_entries(c: &mut Criterion) { 
+    let (start_blockhash, entries) = slot_entries();
+    let tx_sig = Signature::default();
+    assert!(verify_entries(&start_blockhash, &entries, &tx_sig));
+
+    let mut group = c.benchmark_group("poh");
+    group.sample_size(10);
+    group.bench_function("verify_entries", |b| b.iter(|| verify_entries(&start_blockhash, &entries, &tx_sig)));
+    group.bench_function("verify_entries_parallel", |b| b.iter(|| verify_entries_parallel(&start_blockhash, &entries, &tx_sig)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_entries);
+criterion_main!(benches);
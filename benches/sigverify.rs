@@ -0,0 +1,31 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use lightnode::sigverify::{verify_transaction_signatures, verify_transactions_batch};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, VersionedTransaction},
+    vote::{instruction::vote, state::Vote},
+};
+
+// about the vote txs of a mainnet block
+const NUM_VOTES: usize = 500;
+
+fn vote_tx(slot: u64) -> VersionedTransaction { 
+    let node = Keypair::new();
+    let vote_account = Keypair::new().pubkey();
+    let ix = vote(&vote_account, &node.pubkey(), Vote::new(vec![slot], Hash::new_unique()));
+    VersionedTransaction::from(Transaction::new_signed_with_payer(&[ix], Some(&node.pubkey()), &[&node], Hash::new_unique()))
+}
+
+fn bench_sigverify(c: &mut Criterion) { 
+    let txs = (0..NUM_VOTES as u64).map(vote_tx).collect::<Vec<_>>();
+    assert!(verify_transactions_batch(&txs).iter().all(|result| result.is_ok()));
+
+    let mut group = c.benchmark_group("sigverify");
+    group.bench_function("verify_transactions_batch", |b| b.iter(|| verify_transactions_batch(&txs)));
+    group.bench_function("verify_transaction_signatures", |b| b.iter(|| txs.iter().map(verify_transaction_signatures).collect::<Vec<_>>()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_sigverify);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use lightnode::{tower_sync::decode_vote_instruction, vote::{decode_transaction, vote_instructions, voted_slot_and_hash}};
+use solana_transaction_status::UiConfirmedBlock;
+
+use common::*;
+
+// the decoding half of `parse_block_votes`: the txs which didnt fail, their vote ixs and the voted slots
+fn extract_votes(block: &UiConfirmedBlock) -> usize { 
+    block.transactions.iter()
+        .flatten()
+        .filter(|tx| tx.meta.as_ref().map_or(true, |meta| meta.err.is_none()))
+        .filter_map(|tx| decode_transaction(&tx.transaction).ok())
+        .map(|tx| vote_instructions(&tx.message).iter()
+            .filter_map(|ix| decode_vote_instruction(&ix.data))
+            .filter(|vote_ix| voted_slot_and_hash(vote_ix).is_some())
+            .count())
+        .sum()
+}
+
+fn bench_parse_block_votes(c: &mut Criterion) { 
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = fixture_client();
+    assert_eq!(runtime.block_on(client.parse_block_votes(FIXTURE_SLOT, 1)).unwrap().len(), 3);
+
+    // the fixture's txs over and over, about as many as a mainnet block has
+    let mut block = fixture_block();
+    let transactions = block.transactions.take().unwrap();
+    block.transactions = Some(transactions.iter().cycle().take(transactions.len() * 250).cloned().collect());
+    assert_eq!(extract_votes(&block), 750);
+
+    let mut group = c.benchmark_group("votes");
+    group.bench_function("parse_block_votes", |b| b.iter(|| runtime.block_on(client.parse_block_votes(FIXTURE_SLOT, 1)).unwrap()));
+    group.bench_function("extract_votes", |b| b.iter(|| extract_votes(&block)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_block_votes);
+criterion_main!(benches);
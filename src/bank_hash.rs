@@ -0,0 +1,184 @@
+use solana_sdk::{account::Account, blake3, hash::{Hash, Hasher, hashv}, pubkey::Pubkey};
+
+use crate::{error::LightNodeError, vote::ParsedVote};
+
+// number of hashes combined into each node of the accounts delta merkle tree
+const MERKLE_FANOUT: usize = 16;
+
+/// the changes of a slot which go into its bank hash
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotChanges { 
+    // every account written in the slot, with its state at the end of the slot
+    pub changed_accounts: Vec<(Pubkey, Account)>,
+    pub signature_count: u64,
+    pub last_blockhash: Hash,
+}
+
+/// the hash of an account in the accounts delta (a closed account hashes to the default hash)
+pub fn hash_account(pubkey: &Pubkey, account: &Account) -> Hash { 
+    if account.lamports == 0 { 
+        return Hash::default();
+    }
+    let hash = blake3::hashv(&[
+        &account.lamports.to_le_bytes(),
+        &account.rent_epoch.to_le_bytes(),
+        &account.data,
+        &[account.executable as u8],
+        account.owner.as_ref(),
+        pubkey.as_ref(),
+    ]);
+    Hash::new_from_array(hash.0)
+}
+
+/// merkle root (with a fanout of 16) over the hashes of the changed accounts sorted by pubkey
+pub fn compute_account_delta_hash(changed_accounts: &[(Pubkey, Account)]) -> Hash { 
+    let mut accounts = changed_accounts.iter().collect::<Vec<_>>();
+    // an account written more than once is only hashed with its last state
+    accounts.reverse();
+    accounts.sort_by_key(|(pubkey, _)| *pubkey);
+    accounts.dedup_by_key(|(pubkey, _)| *pubkey);
+
+    let mut hashes = accounts.iter()
+        .map(|(pubkey, account)| hash_account(pubkey, account))
+        .collect::<Vec<_>>();
+    if hashes.is_empty() { 
+        return Hasher::default().result();
+    }
+    // like solana's merkle root loop the first level is always hashed, so a single account is
+    // hashed again rather than being the root itself
+    loop { 
+        hashes = hashes.chunks(MERKLE_FANOUT)
+            .map(|chunk| { 
+                let mut hasher = Hasher::default();
+                for hash in chunk.iter() { 
+                    hasher.hash(hash.as_ref());
+                }
+                hasher.result()
+            })
+            .collect();
+        if hashes.len() == 1 { 
+            break;
+        }
+    }
+    hashes[0]
+}
+
+pub fn bank_hash(parent_bank_hash: &Hash, accounts_delta_hash: &Hash, signature_count_buf: &[u8], last_blockhash: &Hash) -> Hash { 
+    hashv(&[
+        parent_bank_hash.as_ref(),
+        accounts_delta_hash.as_ref(),
+        signature_count_buf,
+        last_blockhash.as_ref(),
+    ])
+}
+
+/// recomputes the bank hash of a slot from its changes, which should match the bank hash the slot
+/// was voted on with (slots where the epoch accounts hash is mixed in wont match)
+pub fn verify_bank_hash(parent_bank_hash: &Hash, slot_changes: &SlotChanges) -> Hash { 
+    let accounts_delta_hash = compute_account_delta_hash(&slot_changes.changed_accounts);
+    bank_hash(parent_bank_hash, &accounts_delta_hash, &slot_changes.signature_count.to_le_bytes(), &slot_changes.last_blockhash)
+}
+
+/// checks the bank hash recomputed from the slot changes against the one `vote` voted for
+pub fn verify_voted_bank_hash(parent_bank_hash: &Hash, slot_changes: &SlotChanges, vote: &ParsedVote) -> Result<Hash, LightNodeError> { 
+    let computed = verify_bank_hash(parent_bank_hash, slot_changes);
+    if computed != vote.bank_hash { 
+        return Err(LightNodeError::BankHashMismatch { slot: vote.slot, computed, voted: vote.bank_hash });
+    }
+    Ok(computed)
+}
+
+#[cfg(test)]
+mod tests { 
+    use super::*;
+    use crate::vote::VoteSource;
+
+    fn account(lamports: u64) -> Account { 
+        Account { lamports, data: vec![1, 2, 3], owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+    }
+
+    fn hash_level(hashes: &[Hash]) -> Hash { 
+        let mut hasher = Hasher::default();
+        for hash in hashes { 
+            hasher.hash(hash.as_ref());
+        }
+        hasher.result()
+    }
+
+    #[test]
+    fn test_empty_delta() { 
+        assert_eq!(compute_account_delta_hash(&[]), Hasher::default().result());
+    }
+
+    #[test]
+    fn test_single_account() { 
+        let pubkey = Pubkey::new_unique();
+        let account = account(10);
+        let account_hash = hash_account(&pubkey, &account);
+        // the root is the hash of the account hash, not the account hash itself
+        assert_eq!(compute_account_delta_hash(&[(pubkey, account)]), hash_level(&[account_hash]));
+        assert_ne!(hash_level(&[account_hash]), account_hash);
+    }
+
+    #[test]
+    fn test_zero_lamport_accounts() { 
+        let pubkey = Pubkey::new_unique();
+        let closed = account(0);
+        assert_eq!(hash_account(&pubkey, &closed), Hash::default());
+        assert_eq!(compute_account_delta_hash(&[(pubkey, closed.clone())]), hash_level(&[Hash::default()]));
+
+        // only the last write of an account counts, closing it after a write hashes it as closed
+        let changes = [(pubkey, account(10)), (pubkey, closed)];
+        assert_eq!(compute_account_delta_hash(&changes), hash_level(&[Hash::default()]));
+    }
+
+    #[test]
+    fn test_more_than_fanout_accounts() { 
+        let mut changes = (0..MERKLE_FANOUT + 1)
+            .map(|i| (Pubkey::new_unique(), account(i as u64 + 1)))
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|(pubkey, _)| *pubkey);
+        let account_hashes = changes.iter().map(|(pubkey, account)| hash_account(pubkey, account)).collect::<Vec<_>>();
+        let first_level = account_hashes.chunks(MERKLE_FANOUT).map(hash_level).collect::<Vec<_>>();
+        assert_eq!(first_level.len(), 2);
+        let expected = hash_level(&first_level);
+
+        // the order changes are written in doesnt matter
+        changes.reverse();
+        assert_eq!(compute_account_delta_hash(&changes), expected);
+    }
+
+    #[test]
+    fn test_verify_voted_bank_hash() { 
+        let parent_bank_hash = Hash::new_unique();
+        let slot_changes = SlotChanges { 
+            changed_accounts: vec![(Pubkey::new_unique(), account(10))],
+            signature_count: 2,
+            last_blockhash: Hash::new_unique(),
+        };
+        let computed = verify_bank_hash(&parent_bank_hash, &slot_changes);
+        let mut vote = ParsedVote { 
+            node_pubkey: None,
+            vote_account: Pubkey::new_unique(),
+            slot: 10,
+            bank_hash: computed,
+            stake: 0,
+            signature_verified: false,
+            voter_authorized: false,
+            timestamp: None,
+            switch_proof_hash: None,
+            voted_slots: vec![(10, 1)],
+            root: None,
+            slot_hash_mismatch: false,
+            signed_vote: None,
+            source: VoteSource::Subscription,
+        };
+        assert_eq!(verify_voted_bank_hash(&parent_bank_hash, &slot_changes, &vote).unwrap(), computed);
+
+        vote.bank_hash = Hash::new_unique();
+        match verify_voted_bank_hash(&parent_bank_hash, &slot_changes, &vote) { 
+            Err(LightNodeError::BankHashMismatch { slot: 10, computed: x, voted }) => assert_eq!((x, voted), (computed, vote.bank_hash)),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}};
+
+use async_trait::async_trait;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiConfirmedBlock;
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::rpc::GetBlockOptions;
+use crate::transport::RpcTransport;
+
+/// where a client's blocks and block headers come from (see `LightClient::with_block_source`)
+#[async_trait]
+pub trait BlockSource: Send + Sync { 
+    async fn get_block(&self, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError>;
+    /// the bincode serialized `BlockHeader`, like `getBlockHeaders` returns it
+    async fn get_block_headers(&self, slot: u64, signature: Signature) -> Result<Vec<u8>, LightNodeError>;
+}
+
+/// straight from the client's rpc (without the quorum checks)
+#[async_trait]
+impl<T: RpcTransport> BlockSource for LightClient<T> { 
+    async fn get_block(&self, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        self.get_block_with_options_from(&self.transport, slot, options).await
+    }
+
+    async fn get_block_headers(&self, slot: u64, signature: Signature) -> Result<Vec<u8>, LightNodeError> { 
+        self.get_block_headers_from(&self.transport, slot, signature).await
+    }
+}
+
+/// blocks recorded to a directory, `<slot>.json` is the `getBlock` result and `<slot>.headers` the
+/// raw block headers. the options arent applied, the blocks are returned as they were recorded
+pub struct FileBlockSource { 
+    pub dir: PathBuf,
+}
+
+impl FileBlockSource { 
+    pub fn new(dir: impl AsRef<Path>) -> Self { 
+        FileBlockSource { dir: dir.as_ref().to_path_buf() }
+    }
+
+    fn read(&self, slot: u64, extension: &str) -> Result<Vec<u8>, LightNodeError> { 
+        match fs::read(self.dir.join(format!("{}.{}", slot, extension))) { 
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(LightNodeError::BlockUnavailable(slot)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// records a block (eg, fetched with `BlockSource::get_block` from an rpc) as a fixture
+    pub fn save_block(&self, slot: u64, block: &UiConfirmedBlock) -> Result<(), LightNodeError> { 
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(format!("{}.json", slot)), serde_json::to_vec(block)?)?;
+        Ok(())
+    }
+
+    pub fn save_block_headers(&self, slot: u64, block_headers: &[u8]) -> Result<(), LightNodeError> { 
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(format!("{}.headers", slot)), block_headers)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BlockSource for FileBlockSource { 
+    async fn get_block(&self, slot: u64, _options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        Ok(serde_json::from_slice(&self.read(slot, "json")?)?)
+    }
+
+    // the headers of every tx in the block are the same so the signature isnt needed
+    async fn get_block_headers(&self, slot: u64, _signature: Signature) -> Result<Vec<u8>, LightNodeError> { 
+        self.read(slot, "headers")
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::HashMap, path::Path, sync::{Arc, Mutex, RwLock, atomic::{AtomicU64, Ordering}}, time::{Duration, Instant}};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::{CommitmentConfig, CommitmentLevel}, pubkey::Pubkey};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::block_source::BlockSource;
+use crate::config::LightNodeConfig;
+use crate::error::LightNodeError;
+use crate::leader_schedule::LeaderScheduleCache;
+use crate::metrics::Metrics;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::slot_hashes::{SlotHashHistory, SuspiciousVote};
+use crate::stake::{EpochStakes, StakeSource, Threshold, stakes_from_leader_schedule, stakes_from_vote_accounts};
+use crate::failover::FailoverTransport;
+use crate::tower::TowerTracker;
+use crate::vote_tracker::{EquivocationReport, RootTracker, VoteTracker};
+use crate::watch::{ConfirmationEvent, ConfirmationHooks};
+use crate::transport::{HttpTransport, RpcTransport, build_http_client};
+
+pub struct LightClient<T: RpcTransport = HttpTransport> { 
+    pub endpoint: String,
+    pub ws_endpoint: String,
+    pub transport: T,
+    // only used by the cli to send txs, every request of the client goes through `transport`
+    pub rpc_client: RpcClient,
+    pub retry_policy: RetryPolicy,
+    pub request_timeout: Duration,
+    pub block_wait_timeout: Duration,
+    pub max_batch_size: usize,
+    pub encoding: UiTransactionEncoding,
+    pub commitment: CommitmentLevel,
+    // every request over `transport` goes through this
+    pub rate_limiter: RateLimiter,
+    pub ticks_per_slot: u64,
+    pub hashes_per_tick: Option<u64>,
+    pub max_block_time_drift: Duration,
+    pub verify_leader: bool,
+    pub confirmation_threshold: Threshold,
+    pub quorum: usize,
+    // the primary and the endpoints checked against it when quorum > 1, each on its own (no failover)
+    pub(crate) quorum_transports: Vec<HttpTransport>,
+    // stakes of the current epoch (and when they were fetched), fetched lazily
+    leader_stakes: RwLock<Option<(Instant, Arc<EpochStakes>)>>,
+    pub stake_cache_ttl: Duration,
+    pub(crate) leader_schedules: LeaderScheduleCache,
+    // lookup table address -> addresses
+    pub(crate) lookup_tables: RwLock<HashMap<Pubkey, Arc<Vec<Pubkey>>>>,
+    // (vote account, epoch) -> authorized voter
+    pub(crate) authorized_voters: RwLock<HashMap<(Pubkey, u64), Option<Pubkey>>>,
+    // every vote parsed out of a block goes through this
+    pub(crate) vote_tracker: Mutex<VoteTracker>,
+    pub(crate) equivocations: Mutex<Vec<EquivocationReport>>,
+    // (epoch, roots of the parsed votes) weighted with the stakes of that epoch
+    pub(crate) root_tracker: Mutex<Option<(u64, RootTracker)>>,
+    pub metrics: Metrics,
+    // vote account -> on-chain tower, see `refresh_towers`
+    pub(crate) towers: RwLock<TowerTracker>,
+    pub(crate) slot_hashes: RwLock<SlotHashHistory>,
+    pub(crate) suspicious_votes: Mutex<Vec<SuspiciousVote>>,
+    // replaces the rpc for blocks and block headers (eg, recorded fixtures)
+    pub(crate) block_source: Option<Arc<dyn BlockSource>>,
+    // called by the watchers as slots are confirmed and rooted
+    pub(crate) confirmation_hooks: ConfirmationHooks,
+    // id of the next JSON-RPC request
+    next_id: AtomicU64,
+}
+
+impl LightClient<HttpTransport> { 
+    pub fn new(endpoint: &str) -> Self { 
+        LightClient::from_config(&LightNodeConfig::new(endpoint))
+    }
+
+    pub fn from_config(config: &LightNodeConfig) -> Self { 
+        LightClient::with_transport(config, HttpTransport::with_timeout(&config.endpoint, config.request_timeout))
+    }
+}
+
+impl LightClient<FailoverTransport> { 
+    /// sends the JSON-RPC requests to the first healthy endpoint of `config.endpoints()`
+    /// (or spreads them over the endpoints, see `config.failover_policy`)
+    /// (`rpc_client` only ever uses the primary endpoint)
+    pub fn with_failover(config: &LightNodeConfig) -> Self { 
+        let transport = FailoverTransport::new(&config.endpoints(), config.request_timeout, config.health_check_interval)
+            .with_policy(config.failover_policy);
+        LightClient::with_transport(config, transport)
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    pub fn with_transport(config: &LightNodeConfig, transport: T) -> Self { 
+        let quorum_transports = if config.quorum > 1 { 
+            let client = build_http_client(config.request_timeout);
+            config.endpoints().iter()
+                .take(config.quorum)
+                .map(|endpoint| HttpTransport::with_client(endpoint, client.clone()))
+                .collect()
+        } else { 
+            vec![]
+        };
+
+        LightClient { 
+            endpoint: config.endpoint.clone(),
+            ws_endpoint: config.ws_endpoint.clone(),
+            transport,
+            rpc_client: RpcClient::new_with_timeout_and_commitment(
+                config.endpoint.clone(),
+                config.request_timeout,
+                CommitmentConfig { commitment: config.commitment },
+            ),
+            retry_policy: config.retry_policy.clone(),
+            request_timeout: config.request_timeout,
+            block_wait_timeout: config.block_wait_timeout,
+            max_batch_size: config.max_batch_size,
+            encoding: config.encoding,
+            commitment: config.commitment,
+            rate_limiter: RateLimiter::new(config.rate_limit.clone()),
+            ticks_per_slot: config.ticks_per_slot,
+            hashes_per_tick: config.hashes_per_tick,
+            max_block_time_drift: config.max_block_time_drift,
+            verify_leader: config.verify_leader,
+            confirmation_threshold: config.confirmation_threshold,
+            quorum: config.quorum,
+            quorum_transports,
+            leader_stakes: RwLock::new(None),
+            stake_cache_ttl: config.stake_cache_ttl,
+            leader_schedules: LeaderScheduleCache::default(),
+            lookup_tables: RwLock::new(HashMap::new()),
+            authorized_voters: RwLock::new(HashMap::new()),
+            vote_tracker: Mutex::new(VoteTracker::new(config.equivocation_window)),
+            equivocations: Mutex::new(vec![]),
+            root_tracker: Mutex::new(None),
+            metrics: Metrics::new(),
+            towers: RwLock::new(TowerTracker::new()),
+            slot_hashes: RwLock::new(SlotHashHistory::new()),
+            suspicious_votes: Mutex::new(vec![]),
+            block_source: None,
+            confirmation_hooks: ConfirmationHooks::default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// reads the blocks and block headers from `source` instead of the rpc
+    /// (everything else, eg, stakes and the leader schedule, still comes from the rpc)
+    pub fn with_block_source(mut self, source: impl BlockSource + 'static) -> Self { 
+        self.block_source = Some(Arc::new(source));
+        self
+    }
+
+    /// calls `hook` from `watch_slots` / `watch_and_verify` (and `slot_results`) when a slot is optimistically
+    /// confirmed and when the root advances, hooks run on the watcher so they should return quickly
+    pub fn with_confirmation_hook(mut self, hook: impl Fn(&ConfirmationEvent) + Send + Sync + 'static) -> Self { 
+        self.confirmation_hooks.push(Box::new(hook));
+        self
+    }
+
+    pub(crate) fn next_request_id(&self) -> u64 { 
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// the equivocations found in the blocks parsed so far (each one is only returned once)
+    pub fn take_equivocations(&self) -> Vec<EquivocationReport> { 
+        std::mem::take(&mut *self.equivocations.lock().unwrap())
+    }
+
+    /// adds the votes saved by `save_votes` in `dir` to the ones checked for equivocations
+    pub fn load_votes(&self, dir: &Path) -> Result<(), LightNodeError> { 
+        self.vote_tracker.lock().unwrap().load(dir)
+    }
+
+    /// saves the votes checked for equivocations to `dir` (next to the watcher's `WatchState`)
+    pub fn save_votes(&self, dir: &Path) -> Result<(), LightNodeError> { 
+        self.vote_tracker.lock().unwrap().save(dir)
+    }
+
+    /// the highest slot rooted by a supermajority of the votes parsed so far (this epoch)
+    pub fn current_root(&self) -> Option<u64> { 
+        self.root_tracker.lock().unwrap().as_ref()
+            .map(|(_, tracker)| tracker.current_root())
+            .filter(|root| *root > 0)
+    }
+
+    /// returns the cached stake map, fetching it on first use and once its older than `stake_cache_ttl`
+    pub async fn leader_stakes(&self) -> Result<Arc<EpochStakes>, LightNodeError> { 
+        if let Some((fetched_at, stakes)) = self.leader_stakes.read().unwrap().as_ref() { 
+            if fetched_at.elapsed() < self.stake_cache_ttl { 
+                return Ok(stakes.clone());
+            }
+        }
+        self.refresh_stakes().await
+    }
+
+    /// refetches the stake map from `getVoteAccounts` and replaces the cache
+    pub async fn refresh_stakes(&self) -> Result<Arc<EpochStakes>, LightNodeError> { 
+        let epoch_info = self.get_epoch_info().await?;
+        let vote_accounts = self.get_vote_accounts().await?;
+        let stakes = Arc::new(EpochStakes { 
+            epoch: epoch_info.epoch,
+            slot: epoch_info.absolute_slot,
+            source: StakeSource::VoteAccounts,
+            stakes: stakes_from_vote_accounts(&vote_accounts)?,
+        });
+        tracing::debug!(epoch = stakes.epoch, slot = stakes.slot, "fetched the stakes of {} vote accounts", stakes.stakes.len());
+        *self.leader_stakes.write().unwrap() = Some((Instant::now(), stakes.clone()));
+
+        Ok(stakes)
+    }
+
+    /// the stakes of the epoch containing `slot`
+    pub async fn stakes_for_slot(&self, slot: u64) -> Result<Arc<EpochStakes>, LightNodeError> { 
+        let epoch = self.epoch_schedule().await?.get_epoch(slot);
+        self.stakes_for_epoch(epoch).await
+    }
+
+    /// the stakes of `epoch`, exact for the current epoch and estimated from the leader schedule for any
+    /// other (`getVoteAccounts` only has the present), see `EpochStakes::source`
+    pub async fn stakes_for_epoch(&self, epoch: u64) -> Result<Arc<EpochStakes>, LightNodeError> { 
+        let mut current = self.leader_stakes().await?;
+        // the cache is stale once the cluster moves into a new epoch
+        if epoch > current.epoch { 
+            current = self.refresh_stakes().await?;
+        }
+        if epoch == current.epoch { 
+            return Ok(current);
+        }
+
+        let first_slot = self.epoch_schedule().await?.get_first_slot_in_epoch(epoch);
+        let schedule = self.leader_schedule(first_slot).await?;
+        let stakes = stakes_from_leader_schedule(&schedule, &current.stakes);
+        if stakes.is_empty() { 
+            return Err(LightNodeError::HistoricalStakesUnavailable { epoch, current_epoch: current.epoch });
+        }
+        tracing::debug!("the stakes of epoch {} are estimated from its leader schedule (current epoch is {})", epoch, current.epoch);
+        Ok(Arc::new(EpochStakes { epoch, slot: current.slot, source: StakeSource::LeaderSchedule, stakes }))
+    }
+}
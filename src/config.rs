@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use solana_sdk::{clock::DEFAULT_TICKS_PER_SLOT, commitment_config::CommitmentLevel};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::failover::FailoverPolicy;
+use crate::rate_limit::RateLimit;
+use crate::retry::RetryPolicy;
+use crate::stake::Threshold;
+use crate::vote_tracker::DEFAULT_VOTE_WINDOW;
+
+pub const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:8002";
+// env var used to point the light node at an RPC (can include an api key)
+// a comma separated list is used as fallbacks in that order
+pub const ENDPOINT_ENV: &str = "LIGHTNODE_ENDPOINT";
+// defaults to the http endpoint with a ws scheme
+// (local validators serve websockets on the rpc port + 1, so set this for those)
+pub const WS_ENDPOINT_ENV: &str = "LIGHTNODE_WS_ENDPOINT";
+// most providers cap the number of requests in a single batch
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 20;
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// vote timestamps are second resolution and validators clocks drift so this is loose
+pub const DEFAULT_MAX_BLOCK_TIME_DRIFT: Duration = Duration::from_secs(60);
+// `getVoteAccounts` is a heavy call, the stakes only change at epoch boundaries anyway (~2 days on mainnet)
+pub const DEFAULT_STAKE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+// a slot is 400ms, a block which still isnt available after this likely wont be
+pub const DEFAULT_BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `http` -> `ws` and `https` -> `wss`
+pub fn ws_endpoint_from_http(endpoint: &str) -> String { 
+    if let Some(rest) = endpoint.strip_prefix("https://") { 
+        format!("wss://{}", rest)
+    } else if let Some(rest) = endpoint.strip_prefix("http://") { 
+        format!("ws://{}", rest)
+    } else { 
+        endpoint.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightNodeConfig { 
+    pub endpoint: String,
+    // used for subscriptions (eg, watching new slots)
+    pub ws_endpoint: String,
+    // tried in order when the primary endpoint fails
+    pub fallback_endpoints: Vec<String>,
+    // how long an endpoint which failed is skipped before its probed with `getHealth`
+    pub health_check_interval: Duration,
+    // how requests are spread over the primary and the fallbacks
+    pub failover_policy: FailoverPolicy,
+    // number of endpoints (the primary and then the fallbacks) which have to agree on
+    // blocks and block headers, 1 trusts the primary alone. with a quorum those are fetched
+    // from each endpoint directly, they dont fail over
+    pub quorum: usize,
+    // applied to transient transport failures of the JSON-RPC calls
+    pub retry_policy: RetryPolicy,
+    // max number of requests sent in a single JSON-RPC batch
+    pub max_batch_size: usize,
+    // encoding of the txs in fetched blocks, base58, base64 or json (jsonParsed drops the data the signatures are over)
+    pub encoding: UiTransactionEncoding,
+    // commitment of the blocks, block headers and slots queried (blocks cant be fetched at processed)
+    pub commitment: CommitmentLevel,
+    // timeout of a single http request (both the JSON-RPC calls and the `RpcClient` ones)
+    pub request_timeout: Duration,
+    // how long a block which isnt available yet is polled for before giving up with `Timeout`
+    pub block_wait_timeout: Duration,
+    // client side limit on the requests sent, None for no limit
+    pub rate_limit: Option<RateLimit>,
+    // expected PoH structure of a slot, hashes_per_tick isnt checked when None
+    pub ticks_per_slot: u64,
+    pub hashes_per_tick: Option<u64>,
+    // how far the rpc's block time can be from the stake weighted vote timestamp before its flagged
+    pub max_block_time_drift: Duration,
+    // how long the stake map from `getVoteAccounts` is reused before its refetched
+    pub stake_cache_ttl: Duration,
+    // slots of parsed votes kept to catch a vote account voting twice for a slot
+    pub equivocation_window: u64,
+    // check a verified block was produced by the leader scheduled for its slot (off by default, it fetches
+    // the leader schedule and the block's rewards)
+    pub verify_leader: bool,
+    // fraction of the total stake which has to vote on a slot's bank hash for it to verify
+    pub confirmation_threshold: Threshold,
+}
+
+impl LightNodeConfig { 
+    pub fn new(endpoint: &str) -> Self { 
+        LightNodeConfig { 
+            endpoint: endpoint.to_string(),
+            ws_endpoint: ws_endpoint_from_http(endpoint),
+            fallback_endpoints: vec![],
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+            failover_policy: FailoverPolicy::Failover,
+            quorum: 1,
+            retry_policy: RetryPolicy::default(),
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            encoding: UiTransactionEncoding::Base58, // better for deserialzing
+            commitment: CommitmentLevel::Confirmed,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            block_wait_timeout: DEFAULT_BLOCK_WAIT_TIMEOUT,
+            rate_limit: None,
+            ticks_per_slot: DEFAULT_TICKS_PER_SLOT,
+            hashes_per_tick: None,
+            max_block_time_drift: DEFAULT_MAX_BLOCK_TIME_DRIFT,
+            stake_cache_ttl: DEFAULT_STAKE_CACHE_TTL,
+            equivocation_window: DEFAULT_VOTE_WINDOW,
+            verify_leader: false,
+            confirmation_threshold: Threshold::SUPERMAJORITY,
+        }
+    }
+
+    /// reads the endpoints from `LIGHTNODE_ENDPOINT`, defaulting to the local cluster
+    pub fn from_env() -> Self { 
+        let endpoints = std::env::var(ENDPOINT_ENV).unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string());
+        LightNodeConfig::from_endpoints(&endpoints)
+    }
+
+    /// a comma separated list of endpoints, the first one is the primary and the rest are fallbacks
+    /// (the ws endpoint is still read from `LIGHTNODE_WS_ENDPOINT` when its set)
+    pub fn from_endpoints(endpoints: &str) -> Self { 
+        let mut endpoints = endpoints.split(',')
+            .map(|endpoint| endpoint.trim())
+            .filter(|endpoint| !endpoint.is_empty());
+
+        let mut config = LightNodeConfig::new(endpoints.next().unwrap_or(DEFAULT_ENDPOINT));
+        config.fallback_endpoints = endpoints.map(|endpoint| endpoint.to_string()).collect();
+        if let Ok(ws_endpoint) = std::env::var(WS_ENDPOINT_ENV) { 
+            config.ws_endpoint = ws_endpoint;
+        }
+        config
+    }
+
+    /// the primary endpoint followed by the fallbacks
+    pub fn endpoints(&self) -> Vec<String> { 
+        std::iter::once(self.endpoint.clone())
+            .chain(self.fallback_endpoints.iter().cloned())
+            .collect()
+    }
+}
+
+impl Default for LightNodeConfig { 
+    fn default() -> Self { 
+        LightNodeConfig::new(DEFAULT_ENDPOINT)
+    }
+}
@@ -0,0 +1,208 @@
+use rayon::prelude::*;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{EntryProof, PartialEntry};
+use solana_entry::poh::Poh;
+use solana_sdk::hash::Hash;
+use solana_sdk::hash::hashv;
+
+use crate::error::LightNodeError;
+
+// from merkle-tree crate
+const LEAF_PREFIX: &[u8] = &[0];
+macro_rules! hash_leaf {
+    {$d:ident} => {
+        hashv(&[LEAF_PREFIX, $d])
+    }
+}
+
+pub fn next_hash_with_tx_hash(
+    start_hash: &Hash,
+    num_hashes: u64,
+    transaction_hash: Option<Hash>,
+) -> Hash {
+    if num_hashes == 0 && transaction_hash.is_none() {
+        return *start_hash;
+    }
+
+    let mut poh = Poh::new(*start_hash, None);
+    poh.hash(num_hashes.saturating_sub(1));
+    if transaction_hash.is_none() {
+        poh.tick().unwrap().hash
+    } else {
+        poh.record(transaction_hash.unwrap()).unwrap().hash
+    }
+}
+
+/// checks the merkle proof of `tx_sig` in the first merkle entry
+/// returns None when there is no merkle entry to check against
+pub fn verify_tx_proof(entries: &[EntryProof], tx_sig: &Signature) -> Option<bool> { 
+    for entry in entries.iter() {
+        if let EntryProof::MerkleEntry(x) = entry { 
+            let leaf = tx_sig.as_ref();
+            let candidate = hash_leaf!(leaf);
+            // when len == 1 this does nothing
+            return Some(x.proof.verify(candidate));
+        }
+    }
+    None
+}
+
+fn verify_entry(start_hash: &Hash, entry: &EntryProof, tx_sig: &Signature) -> bool { 
+    match entry { 
+        EntryProof::PartialEntry(x) => {
+            next_hash_with_tx_hash(start_hash, x.num_hashes, x.transaction_hash) == x.hash
+        }, 
+        EntryProof::MerkleEntry(x) => {
+            let tx_hash = if let Some(hash) = x.proof.root() {
+                hash
+            } else { 
+                let tx_sig_ref = tx_sig.as_ref();
+                hash_leaf!(tx_sig_ref)
+            };
+            next_hash_with_tx_hash(start_hash, x.num_hashes, Some(tx_hash)) == x.hash
+        }
+    }
+}
+
+/// checks the entries form a valid PoH path starting from `start_blockhash`
+pub fn verify_entries(start_blockhash: &Hash, entries: &[EntryProof], tx_sig: &Signature) -> bool { 
+    let genesis = [EntryProof::PartialEntry(PartialEntry {
+        num_hashes: 0,
+        hash: *start_blockhash,
+        transaction_hash: None
+    })];
+    let mut entry_pairs = genesis.iter().chain(entries.iter()).zip(entries.iter());
+    entry_pairs.all(|(x0, x1)| verify_entry(&x0.hash(), x1, tx_sig))
+}
+
+/// `verify_entries` with the hashing split into chunks across the rayon thread pool
+pub fn verify_entries_parallel(start_blockhash: &Hash, entries: &[EntryProof], tx_sig: &Signature) -> bool { 
+    let num_threads = rayon::current_num_threads().max(1);
+    let chunk_size = ((entries.len() + num_threads - 1) / num_threads).max(1);
+
+    // each chunk is chained from the last hash of the chunk before it, so the
+    // chunks stitch together into the same path `verify_entries` checks
+    entries.par_chunks(chunk_size)
+        .enumerate()
+        .all(|(i, chunk)| { 
+            let start_hash = if i == 0 { 
+                *start_blockhash
+            } else { 
+                entries[i * chunk_size - 1].hash()
+            };
+            verify_entries(&start_hash, chunk, tx_sig)
+        })
+}
+
+/// `verify_entries_parallel` on a blocking thread so the hashing doesnt hold up the async runtime,
+/// the entries are handed back with the result
+pub async fn verify_entries_blocking(start_blockhash: Hash, entries: Vec<EntryProof>, tx_sig: Signature) -> Result<(Vec<EntryProof>, bool), LightNodeError> { 
+    tokio::task::spawn_blocking(move || { 
+        let verified = verify_entries_parallel(&start_blockhash, &entries, &tx_sig);
+        (entries, verified)
+    })
+    .await
+    .map_err(|e| LightNodeError::Verification(format!("PoH verification task failed: {}", e)))
+}
+
+/// checks the tick structure of a full slot: `ticks_per_slot` ticks (entries without txs),
+/// `hashes_per_tick` hashes up to each tick when its known, and the last entry being the
+/// tick which produced `blockhash`
+pub fn verify_ticks(entries: &[EntryProof], ticks_per_slot: u64, hashes_per_tick: Option<u64>, blockhash: &Hash) -> Result<(), LightNodeError> { 
+    let mut num_ticks = 0;
+    let mut hashes_since_tick = 0;
+    for (i, entry) in entries.iter().enumerate() { 
+        let (num_hashes, is_tick) = match entry { 
+            EntryProof::PartialEntry(x) => (x.num_hashes, x.transaction_hash.is_none()),
+            EntryProof::MerkleEntry(x) => (x.num_hashes, false),
+        };
+        hashes_since_tick += num_hashes;
+        if !is_tick { 
+            continue;
+        }
+
+        if let Some(hashes_per_tick) = hashes_per_tick { 
+            if hashes_since_tick != hashes_per_tick { 
+                return Err(LightNodeError::InvalidTicks(format!(
+                    "tick {} (entry {}) has {} hashes, expected {}", num_ticks, i, hashes_since_tick, hashes_per_tick
+                )));
+            }
+        }
+        num_ticks += 1;
+        hashes_since_tick = 0;
+    }
+
+    if num_ticks != ticks_per_slot { 
+        return Err(LightNodeError::InvalidTicks(format!("found {} ticks, expected {}", num_ticks, ticks_per_slot)));
+    }
+    match entries.last() { 
+        Some(EntryProof::PartialEntry(x)) if x.transaction_hash.is_none() => { 
+            if x.hash != *blockhash { 
+                return Err(LightNodeError::InvalidTicks(format!("last tick {} doesnt match the blockhash {}", x.hash, blockhash)));
+            }
+        }
+        _ => return Err(LightNodeError::InvalidTicks("last entry is not a tick".to_string())),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests { 
+    use super::*;
+
+    // a tx entry then a tick, over and over
+    fn entries(n: u64) -> (Hash, Vec<EntryProof>) { 
+        let start_blockhash = hashv(&[b"entries"]);
+        let mut hash = start_blockhash;
+        let entries = (0..n)
+            .map(|i| { 
+                let transaction_hash = (i % 2 == 0).then(|| hashv(&[&i.to_le_bytes()]));
+                hash = next_hash_with_tx_hash(&hash, 1 + i % 5, transaction_hash);
+                EntryProof::PartialEntry(PartialEntry { num_hashes: 1 + i % 5, hash, transaction_hash })
+            })
+            .collect();
+        (start_blockhash, entries)
+    }
+
+    // on 4 threads so the 64 entries split into 4 chunks of 16
+    fn verify_entries_4_threads(start_blockhash: &Hash, entries: &[EntryProof]) -> bool { 
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        pool.install(|| verify_entries_parallel(start_blockhash, entries, &Signature::default()))
+    }
+
+    #[test]
+    fn test_verify_entries_parallel_matches_sequential() { 
+        let (start_blockhash, entries) = entries(64);
+        assert!(verify_entries(&start_blockhash, &entries, &Signature::default()));
+        assert!(verify_entries_4_threads(&start_blockhash, &entries));
+
+        // a wrong start only breaks the first chunk
+        let wrong_start = hashv(&[b"wrong"]);
+        assert!(!verify_entries(&wrong_start, &entries, &Signature::default()));
+        assert!(!verify_entries_4_threads(&wrong_start, &entries));
+        assert!(verify_entries_4_threads(&start_blockhash, &entries[..0]));
+    }
+
+    #[test]
+    fn test_verify_entries_parallel_corrupted_entry() { 
+        let (start_blockhash, entries) = entries(64);
+        // the middle of the second chunk, the last entry of the first chunk (the second one starts from its hash)
+        // the first entry of the third chunk and the very last entry
+        for i in [24, 15, 32, 63] { 
+            let mut corrupted = entries.clone();
+            if let EntryProof::PartialEntry(x) = &mut corrupted[i] { 
+                x.num_hashes += 1;
+            }
+            assert!(!verify_entries(&start_blockhash, &corrupted, &Signature::default()), "entry {}", i);
+            assert!(!verify_entries_4_threads(&start_blockhash, &corrupted), "entry {}", i);
+
+            let mut corrupted = entries.clone();
+            if let EntryProof::PartialEntry(x) = &mut corrupted[i] { 
+                x.hash = hashv(&[b"corrupted"]);
+            }
+            assert!(!verify_entries(&start_blockhash, &corrupted, &Signature::default()), "entry {}", i);
+            assert!(!verify_entries_4_threads(&start_blockhash, &corrupted), "entry {}", i);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::{hash::Hash, pubkey::{ParsePubkeyError, Pubkey}, signature::Signature};
+use thiserror::Error;
+
+use crate::transport::TransportError;
+
+#[derive(Debug, Error)]
+pub enum LightNodeError { 
+    #[error("rpc transport error: {0}")]
+    Transport(#[from] TransportError),
+    #[error("rpc transport error after {attempts} attempts: {source}")]
+    RetriesExhausted { attempts: u32, source: TransportError },
+    #[error("json deserialization error: {0}")]
+    Deserialize(#[from] serde_json::Error),
+    #[error("invalid json-rpc response: {0}")]
+    InvalidResponse(String),
+    #[error("json-rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("rpc client error: {0}")]
+    Client(Box<ClientError>),
+    #[error("bincode deserialization error: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("base58 decode error: {0}")]
+    Base58(#[from] bs58::decode::Error),
+    #[error("base64 decode error: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("signature verification failed for {0}")]
+    SignatureVerification(Signature),
+    #[error("block unavailable for slot {0}")]
+    BlockUnavailable(u64),
+    #[error("slot {0} was skipped")]
+    SlotSkipped(u64),
+    #[error("empty block headers for slot {0}")]
+    EmptyBlockHeaders(u64),
+    #[error("invalid block headers for slot {slot}: {source}")]
+    InvalidBlockHeaders { slot: u64, source: bincode::Error },
+    #[error("address lookup table error: {0}")]
+    AddressLookupTable(String),
+    #[error("unsupported transaction encoding: {0}")]
+    UnsupportedEncoding(String),
+    #[error("invalid pubkey: {0}")]
+    InvalidPubkey(#[from] ParsePubkeyError),
+    #[error("leader schedule error: {0}")]
+    LeaderSchedule(String),
+    // a block produced by an identity which wasnt scheduled for its slot
+    #[error("block {slot} was produced by {producer} but the scheduled leader is {expected}")]
+    UnexpectedLeader { slot: u64, expected: Pubkey, producer: Pubkey },
+    // `getVoteAccounts` only has the stakes of the current epoch and none of the leaders in the
+    // epoch's schedule could be matched to a current vote account
+    #[error("stakes of epoch {epoch} are unavailable (current epoch is {current_epoch})")]
+    HistoricalStakesUnavailable { epoch: u64, current_epoch: u64 },
+    #[error("no stake entry for {0}")]
+    MissingStake(String),
+    #[error("invalid confirmation threshold: {0}")]
+    InvalidThreshold(String),
+    // the PoH path hashes but doesnt end at the block's published blockhash
+    #[error("entries of slot {slot} end at {computed} instead of the blockhash {blockhash}")]
+    BlockhashMismatch { slot: u64, computed: Hash, blockhash: Hash },
+    // the bank hash recomputed from the slot's changes isnt the one which was voted for
+    #[error("bank hash of slot {slot} is {computed} but {voted} was voted for")]
+    BankHashMismatch { slot: u64, computed: Hash, voted: Hash },
+    #[error("invalid tick structure: {0}")]
+    InvalidTicks(String),
+    // (endpoint, what it returned) for every endpoint in the quorum
+    #[error("endpoints disagree: {0:?}")]
+    Divergence(Vec<(String, String)>),
+    #[error("verification failed: {0}")]
+    Verification(String),
+    #[error("subscription error: {0}")]
+    Subscription(String),
+    #[error("http server error: {0}")]
+    Server(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+// ClientError is large so its boxed to keep the Result small
+impl From<ClientError> for LightNodeError { 
+    fn from(e: ClientError) -> Self { 
+        LightNodeError::Client(Box::new(e))
+    }
+}
+
+impl LightNodeError { 
+    /// the slot might still verify later: its block or headers just arent available from the rpc yet
+    pub fn is_retryable(&self) -> bool { 
+        matches!(self, LightNodeError::BlockUnavailable(_) | LightNodeError::EmptyBlockHeaders(_) | LightNodeError::Timeout(_))
+    }
+
+    /// the request failed on the way (timeout, 429 or 5xx) and is worth sending again, the same rule
+    /// `rpc_call` retries on. a request which already ran out of retries isnt
+    pub fn is_transient(&self) -> bool { 
+        match self { 
+            LightNodeError::Transport(e) => e.is_transient(),
+            LightNodeError::Timeout(_) => true,
+            LightNodeError::Client(e) => match e.kind() { 
+                ClientErrorKind::Reqwest(e) => { 
+                    e.is_connect() || e.is_timeout() || e.status().map_or(false, |status| status.as_u16() == 429 || status.is_server_error())
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::{sync::{Mutex, atomic::{AtomicUsize, Ordering}}, time::{Duration, Instant}};
+
+use async_trait::async_trait;
+
+use crate::transport::{HttpTransport, RpcTransport, TransportError, build_http_client};
+
+const GET_HEALTH_REQUEST: &str = r#"{"jsonrpc":"2.0","id":1,"method":"getHealth"}"#;
+
+/// which transport a request goes to first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailoverPolicy { 
+    // always the primary, the others are only used when it fails
+    #[default]
+    Failover,
+    // each request starts at the transport after the one the last request started at
+    RoundRobin,
+}
+
+/// sends each request to the first healthy transport, in order
+/// transports which fail (eg, timeouts or 429s) are skipped until they pass a `getHealth` probe
+pub struct FailoverTransport<T: RpcTransport = HttpTransport> { 
+    // in failover order, the first one is the primary
+    transports: Vec<T>,
+    pub policy: FailoverPolicy,
+    // where the next request starts with round robin
+    next_start: AtomicUsize,
+    // when each transport last failed (a request or a probe), None when its healthy
+    unhealthy_since: Mutex<Vec<Option<Instant>>>,
+    pub health_check_interval: Duration,
+}
+
+impl FailoverTransport<HttpTransport> { 
+    pub fn new(endpoints: &[String], timeout: Duration, health_check_interval: Duration) -> Self { 
+        // the endpoints share a single connection pool
+        let client = build_http_client(timeout);
+        let transports = endpoints.iter()
+            .map(|endpoint| HttpTransport::with_client(endpoint, client.clone()))
+            .collect();
+        FailoverTransport::with_transports(transports, health_check_interval)
+    }
+}
+
+impl<T: RpcTransport> FailoverTransport<T> { 
+    pub fn with_transports(transports: Vec<T>, health_check_interval: Duration) -> Self { 
+        assert!(!transports.is_empty(), "failover needs at least one transport");
+        FailoverTransport { 
+            unhealthy_since: Mutex::new(vec![None; transports.len()]),
+            transports,
+            policy: FailoverPolicy::Failover,
+            next_start: AtomicUsize::new(0),
+            health_check_interval,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: FailoverPolicy) -> Self { 
+        self.policy = policy;
+        self
+    }
+
+    /// in failover order
+    pub fn transports(&self) -> &[T] { 
+        &self.transports
+    }
+
+    // the order the transports are tried in for the next request
+    fn order(&self) -> impl Iterator<Item = usize> { 
+        let n = self.transports.len();
+        let start = match self.policy { 
+            FailoverPolicy::Failover => 0,
+            FailoverPolicy::RoundRobin => self.next_start.fetch_add(1, Ordering::Relaxed) % n,
+        };
+        (0..n).map(move |i| (start + i) % n)
+    }
+
+    pub fn is_healthy(&self, i: usize) -> bool { 
+        self.unhealthy_since.lock().unwrap()[i].is_none()
+    }
+
+    fn set_healthy(&self, i: usize, healthy: bool) { 
+        self.unhealthy_since.lock().unwrap()[i] = if healthy { None } else { Some(Instant::now()) };
+    }
+
+    async fn probe(&self, i: usize) -> bool { 
+        let healthy = match self.transports[i].send(GET_HEALTH_REQUEST.to_string()).await { 
+            Ok(resp) => serde_json::from_str::<serde_json::Value>(&resp)
+                .map(|resp| resp.get("result").and_then(|x| x.as_str()) == Some("ok"))
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        tracing::debug!("health check of endpoint {}: healthy = {}", i, healthy);
+        self.set_healthy(i, healthy);
+        healthy
+    }
+}
+
+#[async_trait]
+impl<T: RpcTransport> RpcTransport for FailoverTransport<T> { 
+    async fn send(&self, body: String) -> Result<String, TransportError> { 
+        let unhealthy_since = self.unhealthy_since.lock().unwrap().clone();
+        let now = Instant::now();
+
+        // unhealthy transports (which arent due a probe or failed it) are only tried once all the others failed
+        let mut last_resort = vec![];
+        let mut last_err = None;
+        for i in self.order() { 
+            match unhealthy_since[i] { 
+                None => {},
+                Some(since) if now.duration_since(since) >= self.health_check_interval => { 
+                    if !self.probe(i).await { 
+                        last_resort.push(i);
+                        continue;
+                    }
+                }
+                Some(_) => { 
+                    last_resort.push(i);
+                    continue;
+                }
+            }
+
+            match self.transports[i].send(body.clone()).await { 
+                Ok(resp) => return Ok(resp),
+                Err(e) if e.is_transient() => { 
+                    tracing::debug!("endpoint {} failed, failing over: {}", i, e);
+                    self.set_healthy(i, false);
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        for i in last_resort { 
+            match self.transports[i].send(body.clone()).await { 
+                Ok(resp) => { 
+                    self.set_healthy(i, true);
+                    return Ok(resp);
+                }
+                Err(e) if e.is_transient() => last_err = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| TransportError::Other("no endpoints".to_string())))
+    }
+}
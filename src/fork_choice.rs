@@ -0,0 +1,61 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::stake::StakeInfo;
+use crate::vote::ParsedVote;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaviestFork { 
+    pub tip: u64,
+    // stake whose latest vote is for the tip or one of its ancestors
+    pub stake: u128,
+}
+
+/// picks the fork tip like the validator's heaviest subtree fork choice: each vote account adds its
+/// stake to the slot of its latest vote, a slot weighs its stake plus the stake of all its descendants,
+/// and from the root the heaviest child (the lower slot on ties) is followed down to a leaf
+/// `parents` is slot -> parent slot of the fetched blocks, None when its empty
+pub fn heaviest_fork(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, parents: &HashMap<u64, u64>) -> Option<HeaviestFork> { 
+    let slots = parents.keys().chain(parents.values()).copied().collect::<HashSet<_>>();
+
+    let mut latest_votes: HashMap<Pubkey, u64> = HashMap::new();
+    for vote in votes.iter().filter(|vote| vote.counts() && slots.contains(&vote.slot)) { 
+        let slot = latest_votes.entry(vote.vote_account).or_insert(vote.slot);
+        *slot = (*slot).max(vote.slot);
+    }
+    let mut voted_stake: HashMap<u64, u128> = HashMap::new();
+    for (vote_account, slot) in latest_votes { 
+        *voted_stake.entry(slot).or_default() += stakes.get(&vote_account).map(|x| x.activated_stake as u128).unwrap_or(0);
+    }
+
+    let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+    for (slot, parent) in parents.iter() { 
+        children.entry(*parent).or_default().push(*slot);
+    }
+
+    // a child always has a higher slot than its parent so going from the highest slot down
+    // adds every subtree to its parent after its complete
+    let mut ordered = slots.iter().copied().collect::<Vec<_>>();
+    ordered.sort_by(|a, b| b.cmp(a));
+    let mut weights: HashMap<u64, u128> = HashMap::new();
+    for slot in ordered.iter() { 
+        let weight = *weights.entry(*slot).or_default() + voted_stake.get(slot).copied().unwrap_or(0);
+        weights.insert(*slot, weight);
+        if let Some(parent) = parents.get(slot) { 
+            *weights.entry(*parent).or_default() += weight;
+        }
+    }
+
+    // the roots are the slots without a known parent (more than one when the blocks arent connected)
+    let heaviest = |candidates: &mut dyn Iterator<Item = u64>| candidates
+        .max_by(|a, b| weights[a].cmp(&weights[b]).then(b.cmp(a)));
+    let mut tip = heaviest(&mut slots.iter().copied().filter(|slot| !parents.contains_key(slot)))?;
+    let mut stake = voted_stake.get(&tip).copied().unwrap_or(0);
+    while let Some(child) = children.get(&tip).and_then(|children| heaviest(&mut children.iter().copied())) { 
+        tip = child;
+        stake += voted_stake.get(&tip).copied().unwrap_or(0);
+    }
+
+    Some(HeaviestFork { tip, stake })
+}
@@ -0,0 +1,287 @@
+use std::{collections::HashMap, str::FromStr, sync::{Arc, RwLock}};
+
+use rand_07::distributions::{Distribution, WeightedIndex};
+use rand_chacha::{ChaChaRng, rand_core::SeedableRng};
+use serde::Serialize;
+use solana_sdk::{clock::NUM_CONSECUTIVE_LEADER_SLOTS, epoch_schedule::EpochSchedule, pubkey::Pubkey, reward_type::RewardType};
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::rpc::GetBlockOptions;
+use crate::stake::{node_stakes, vote_stakes_at_epoch};
+use crate::transport::RpcTransport;
+
+// scans usually stay within an epoch or two so older schedules are dropped
+const MAX_CACHED_EPOCHS: usize = 3;
+
+/// leader schedules keyed by epoch (the schedule of an epoch never changes once its known)
+#[derive(Default)]
+pub struct LeaderScheduleCache { 
+    epoch_schedule: RwLock<Option<EpochSchedule>>,
+    // epoch -> leader of each slot in the epoch (by slot index)
+    schedules: RwLock<HashMap<u64, Arc<Vec<Pubkey>>>>,
+}
+
+impl LeaderScheduleCache { 
+    pub fn get(&self, epoch: u64) -> Option<Arc<Vec<Pubkey>>> { 
+        self.schedules.read().unwrap().get(&epoch).cloned()
+    }
+
+    pub fn insert(&self, epoch: u64, schedule: Arc<Vec<Pubkey>>) { 
+        let mut schedules = self.schedules.write().unwrap();
+        schedules.insert(epoch, schedule);
+        while schedules.len() > MAX_CACHED_EPOCHS { 
+            let oldest = *schedules.keys().min().unwrap();
+            schedules.remove(&oldest);
+        }
+    }
+}
+
+/// recomputes the leader schedule of `epoch` from the stakes of the node identities the same way
+/// the validator does: stake weighted sampling seeded with the epoch, each leader gets 4 slots in a row
+/// (the schedule of an epoch uses the stakes from the start of the epoch before it)
+pub fn compute_leader_schedule(epoch: u64, stakes: &HashMap<Pubkey, u64>, epoch_schedule: &EpochSchedule) -> Vec<Pubkey> { 
+    // sorted by stake and then pubkey (both descending) so the sampling doesnt depend on the map's order
+    let mut stakes = stakes.iter()
+        .filter(|(_, stake)| **stake > 0)
+        .map(|(pubkey, stake)| (*pubkey, *stake))
+        .collect::<Vec<_>>();
+    stakes.sort_by(|(l_pubkey, l_stake), (r_pubkey, r_stake)| r_stake.cmp(l_stake).then(r_pubkey.cmp(l_pubkey)));
+    stakes.dedup();
+    if stakes.is_empty() { 
+        return vec![];
+    }
+
+    let mut seed = [0u8; 32];
+    seed[0..8].copy_from_slice(&epoch.to_le_bytes());
+    let rng = &mut ChaChaRng::from_seed(seed);
+    let weighted_index = WeightedIndex::new(stakes.iter().map(|(_, stake)| *stake)).unwrap();
+
+    let mut leader = Pubkey::default();
+    (0..epoch_schedule.get_slots_in_epoch(epoch))
+        .map(|i| { 
+            if i % NUM_CONSECUTIVE_LEADER_SLOTS == 0 { 
+                leader = stakes[weighted_index.sample(rng)].0;
+            }
+            leader
+        })
+        .collect()
+}
+
+/// the rpc's leader schedule of an epoch checked against the one computed from the stakes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeaderScheduleCheck { 
+    pub epoch: u64,
+    // epoch whose stakes the schedule is computed from (the one before it)
+    pub stakes_epoch: u64,
+    pub num_slots: u64,
+    // slots where the rpc's leader isnt the computed one
+    pub mismatched_slots: Vec<u64>,
+}
+
+impl LeaderScheduleCheck { 
+    pub fn verified(&self) -> bool { 
+        self.mismatched_slots.is_empty()
+    }
+}
+
+// the fees of a block go to its leader so the fee reward names the identity which produced it
+fn block_producer(block: &UiConfirmedBlock) -> Result<Option<Pubkey>, LightNodeError> { 
+    let reward = block.rewards.as_ref()
+        .and_then(|rewards| rewards.iter().find(|reward| reward.reward_type == Some(RewardType::Fee)));
+    match reward { 
+        Some(reward) => Ok(Some(Pubkey::from_str(&reward.pubkey)?)),
+        None => Ok(None),
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    pub(crate) async fn epoch_schedule(&self) -> Result<EpochSchedule, LightNodeError> { 
+        if let Some(epoch_schedule) = self.leader_schedules.epoch_schedule.read().unwrap().as_ref() { 
+            return Ok(epoch_schedule.clone());
+        }
+        let epoch_schedule = self.get_epoch_schedule().await?;
+        *self.leader_schedules.epoch_schedule.write().unwrap() = Some(epoch_schedule.clone());
+
+        Ok(epoch_schedule)
+    }
+
+    /// the leader schedule of the epoch containing `slot`, fetched once per epoch
+    pub async fn leader_schedule(&self, slot: u64) -> Result<Arc<Vec<Pubkey>>, LightNodeError> { 
+        let epoch_schedule = self.epoch_schedule().await?;
+        let epoch = epoch_schedule.get_epoch(slot);
+        if let Some(schedule) = self.leader_schedules.get(epoch) { 
+            return Ok(schedule);
+        }
+
+        let leader_schedule = self.get_leader_schedule(slot).await?
+            .ok_or_else(|| LightNodeError::LeaderSchedule(format!("no leader schedule for epoch {}", epoch)))?;
+
+        // identity -> slot indexes in the epoch, flipped into slot index -> identity
+        let mut schedule = vec![Pubkey::default(); epoch_schedule.get_slots_in_epoch(epoch) as usize];
+        for (identity, slot_indexes) in leader_schedule.iter() { 
+            let identity = Pubkey::from_str(identity)?;
+            for i in slot_indexes.iter() { 
+                match schedule.get_mut(*i) { 
+                    Some(leader) => *leader = identity,
+                    None => return Err(LightNodeError::LeaderSchedule(format!("slot index {} out of range for epoch {}", i, epoch))),
+                }
+            }
+        }
+        let schedule = Arc::new(schedule);
+        self.leader_schedules.insert(epoch, schedule.clone());
+
+        Ok(schedule)
+    }
+
+    /// the node identity scheduled to produce the block at `slot`
+    pub async fn leader_for_slot(&self, slot: u64) -> Result<Pubkey, LightNodeError> { 
+        let epoch_schedule = self.epoch_schedule().await?;
+        let (_, slot_index) = epoch_schedule.get_epoch_and_slot_index(slot);
+        let schedule = self.leader_schedule(slot).await?;
+        schedule.get(slot_index as usize)
+            .copied()
+            .ok_or_else(|| LightNodeError::LeaderSchedule(format!("no leader for slot {}", slot)))
+    }
+
+    /// checks the block at `slot` was produced by the leader scheduled for it, returns the leader
+    /// (None when the block has no fee reward to find its producer with, eg, the rpc drops rewards)
+    pub async fn verify_block_leader(&self, slot: u64) -> Result<Option<Pubkey>, LightNodeError> { 
+        let options = GetBlockOptions::new(self.encoding).transaction_details(TransactionDetails::None);
+        let block = self.get_block_with_options(slot, &options).await?;
+        let producer = match block_producer(&block)? { 
+            Some(producer) => producer,
+            None => { 
+                tracing::warn!("block {} has no fee reward to find its producer with, the leader isnt checked", slot);
+                return Ok(None);
+            }
+        };
+
+        let expected = self.leader_for_slot(slot).await?;
+        if producer != expected { 
+            return Err(LightNodeError::UnexpectedLeader { slot, expected, producer });
+        }
+        Ok(Some(producer))
+    }
+
+    /// the slot indexes in `epoch` where the rpc's leader schedule differs from the one computed
+    /// from `stakes` (node identity -> stake of the epoch the schedule was computed from)
+    pub async fn check_leader_schedule(&self, epoch: u64, stakes: &HashMap<Pubkey, u64>) -> Result<Vec<u64>, LightNodeError> { 
+        let epoch_schedule = self.epoch_schedule().await?;
+        let computed = compute_leader_schedule(epoch, stakes, &epoch_schedule);
+        let schedule = self.leader_schedule(epoch_schedule.get_first_slot_in_epoch(epoch)).await?;
+        if computed.len() != schedule.len() { 
+            return Err(LightNodeError::LeaderSchedule(format!(
+                "computed {} slots for epoch {} but the rpc has {}", computed.len(), epoch, schedule.len()
+            )));
+        }
+
+        let mismatches = (0..schedule.len())
+            .filter(|i| computed[*i] != schedule[*i])
+            .map(|i| i as u64)
+            .collect::<Vec<_>>();
+        if !mismatches.is_empty() { 
+            tracing::warn!("leader schedule of epoch {} differs from the computed one in {} slots", epoch, mismatches.len());
+        }
+        Ok(mismatches)
+    }
+
+    /// node identity -> stake at `epoch`, from `getVoteAccounts` for the current epoch and rebuilt from the
+    /// stake delegations and stake history for older ones (a full scan of the stake accounts)
+    pub async fn node_stakes_at_epoch(&self, epoch: u64) -> Result<HashMap<Pubkey, u64>, LightNodeError> { 
+        let current = self.leader_stakes().await?;
+        if epoch == current.epoch { 
+            return Ok(node_stakes(&current.stakes));
+        }
+        if epoch > current.epoch { 
+            return Err(LightNodeError::LeaderSchedule(format!("the stakes of epoch {} arent known yet", epoch)));
+        }
+
+        let stake_history = self.get_stake_history().await?;
+        let delegations = self.get_stake_delegations().await?;
+        // the vote accounts are mapped to the identities they have now
+        let mut stakes = HashMap::new();
+        for (vote_account, stake) in vote_stakes_at_epoch(&delegations, epoch, &stake_history) { 
+            if let Some(stake_info) = current.stakes.get(&vote_account) { 
+                *stakes.entry(stake_info.node_pubkey).or_insert(0) += stake;
+            }
+        }
+        Ok(stakes)
+    }
+
+    /// recomputes the leader schedule of `epoch` and compares it with the rpc's
+    pub async fn verify_leader_schedule(&self, epoch: u64) -> Result<LeaderScheduleCheck, LightNodeError> { 
+        let stakes_epoch = epoch.saturating_sub(1);
+        let stakes = self.node_stakes_at_epoch(stakes_epoch).await?;
+        let epoch_schedule = self.epoch_schedule().await?;
+        let first_slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+        let mismatched_slots = self.check_leader_schedule(epoch, &stakes).await?
+            .into_iter()
+            .map(|i| first_slot + i)
+            .collect();
+        Ok(LeaderScheduleCheck { 
+            epoch,
+            stakes_epoch,
+            num_slots: epoch_schedule.get_slots_in_epoch(epoch),
+            mismatched_slots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests { 
+    use crate::config::LightNodeConfig;
+    use crate::mock::{MockResponse, MockTransport};
+
+    use super::*;
+
+    const EPOCH: u64 = 5;
+
+    fn node(i: u8) -> Pubkey { 
+        Pubkey::new_from_array([i; 32])
+    }
+
+    fn stakes() -> HashMap<Pubkey, u64> { 
+        HashMap::from([(node(1), 600), (node(2), 300), (node(3), 100)])
+    }
+
+    fn epoch_schedule() -> EpochSchedule { 
+        EpochSchedule::custom(64, 64, false)
+    }
+
+    #[test]
+    fn test_compute_leader_schedule() { 
+        // the first leader of each 4 slots, sampled with the validator's ChaCha20 / WeightedIndex
+        let expected = [1, 1, 1, 2, 1, 2, 1, 2, 2, 2, 2, 1, 2, 1, 1, 2];
+        let schedule = compute_leader_schedule(EPOCH, &stakes(), &epoch_schedule());
+        assert_eq!(schedule.len(), 64);
+        for (i, leader) in schedule.iter().enumerate() { 
+            assert_eq!(*leader, node(expected[i / NUM_CONSECUTIVE_LEADER_SLOTS as usize]), "slot index {}", i);
+        }
+
+        // zero stake nodes are never scheduled and the map's order doesnt matter
+        let mut with_unstaked = stakes();
+        with_unstaked.insert(node(4), 0);
+        assert_eq!(compute_leader_schedule(EPOCH, &with_unstaked, &epoch_schedule()), schedule);
+        assert_ne!(compute_leader_schedule(EPOCH + 1, &stakes(), &epoch_schedule()), schedule);
+        assert!(compute_leader_schedule(EPOCH, &HashMap::new(), &epoch_schedule()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_leader_schedule_mismatches() { 
+        let epoch_schedule = epoch_schedule();
+        let mut rpc_schedule = HashMap::<String, Vec<usize>>::new();
+        for (i, leader) in compute_leader_schedule(EPOCH, &stakes(), &epoch_schedule).iter().enumerate() { 
+            // the rpc has node 3 on the leader slots at 8..12 (node 1's)
+            let leader = if (8..12).contains(&i) { node(3) } else { *leader };
+            rpc_schedule.entry(leader.to_string()).or_default().push(i);
+        }
+        let transport = MockTransport::new()
+            .respond("getEpochSchedule", MockResponse::Result(serde_json::to_value(&epoch_schedule).unwrap()))
+            .respond("getLeaderSchedule", MockResponse::Result(serde_json::to_value(&rpc_schedule).unwrap()));
+        let client = LightClient::with_transport(&LightNodeConfig::new("http://localhost:8899"), transport);
+
+        assert_eq!(client.check_leader_schedule(EPOCH, &stakes()).await.unwrap(), vec![8, 9, 10, 11]);
+    }
+}
@@ -0,0 +1,35 @@
+pub mod bank_hash;
+pub mod block_source;
+pub mod client;
+pub mod config;
+pub mod entries;
+pub mod error;
+pub mod failover;
+pub mod fork_choice;
+pub mod leader_schedule;
+pub mod lookup_table;
+pub mod metrics;
+pub mod mock;
+pub mod participation;
+pub mod program_accounts;
+pub mod quorum;
+pub mod rate_limit;
+pub mod retry;
+pub mod rpc;
+pub mod server;
+pub mod sigverify;
+pub mod slot_hashes;
+pub mod stake;
+pub mod state;
+pub mod tower;
+pub mod tower_sync;
+pub mod transport;
+pub mod vote;
+pub mod vote_stream;
+pub mod vote_tracker;
+pub mod watch;
+pub mod verify;
+
+pub use client::LightClient;
+pub use config::LightNodeConfig;
+pub use error::LightNodeError;
@@ -0,0 +1,103 @@
+use std::{borrow::Cow, sync::Arc};
+
+use solana_address_lookup_table_program::state::AddressLookupTable;
+use solana_sdk::{message::VersionedMessage, pubkey::Pubkey};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::transport::RpcTransport;
+
+// max number of accounts in a single `getMultipleAccounts`
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+fn decode_lookup_table(table: &Pubkey, data: &[u8]) -> Result<Arc<Vec<Pubkey>>, LightNodeError> { 
+    let lookup_table = AddressLookupTable::deserialize(data)
+        .map_err(|e| LightNodeError::AddressLookupTable(format!("failed to deserialize table {}: {:?}", table, e)))?;
+    Ok(Arc::new(lookup_table.addresses.to_vec()))
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// returns the addresses stored in a lookup table, cached by table address
+    pub async fn get_lookup_table(&self, table: &Pubkey) -> Result<Arc<Vec<Pubkey>>, LightNodeError> { 
+        if let Some(addresses) = self.lookup_tables.read().unwrap().get(table) { 
+            return Ok(addresses.clone());
+        }
+        self.refresh_lookup_table(table).await
+    }
+
+    pub async fn refresh_lookup_table(&self, table: &Pubkey) -> Result<Arc<Vec<Pubkey>>, LightNodeError> { 
+        let account = self.get_account(table).await?.1
+            .ok_or_else(|| LightNodeError::AddressLookupTable(format!("table {} not found", table)))?;
+        let addresses = decode_lookup_table(table, &account.data)?;
+        self.lookup_tables.write().unwrap().insert(*table, addresses.clone());
+
+        Ok(addresses)
+    }
+
+    /// fetches the tables which arent cached yet with `getMultipleAccounts` (instead of one call per table),
+    /// tables which dont exist (eg, closed ones) are left out and fail when theyre looked up
+    pub async fn prefetch_lookup_tables(&self, tables: &[Pubkey]) -> Result<(), LightNodeError> { 
+        let mut missing = { 
+            let lookup_tables = self.lookup_tables.read().unwrap();
+            tables.iter().filter(|table| !lookup_tables.contains_key(table)).copied().collect::<Vec<_>>()
+        };
+        missing.sort();
+        missing.dedup();
+
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) { 
+            let (_, accounts) = self.get_multiple_accounts(chunk).await?;
+            for (table, account) in chunk.iter().zip(accounts.iter()) { 
+                let account = match account { 
+                    Some(account) => account,
+                    None => { 
+                        tracing::debug!("lookup table {} not found", table);
+                        continue;
+                    }
+                };
+                let addresses = decode_lookup_table(table, &account.data)?;
+                self.lookup_tables.write().unwrap().insert(*table, addresses);
+            }
+        }
+        Ok(())
+    }
+
+    /// the full account key list of a message: static keys, then the writable and readonly
+    /// keys loaded from its address lookup tables (legacy messages borrow their static keys)
+    pub async fn resolve_account_keys<'a>(&self, msg: &'a VersionedMessage) -> Result<Cow<'a, [Pubkey]>, LightNodeError> { 
+        let lookups = match msg { 
+            VersionedMessage::Legacy(_) => return Ok(Cow::Borrowed(msg.static_account_keys())),
+            VersionedMessage::V0(msg) => &msg.address_table_lookups,
+        };
+
+        let tables = lookups.iter().map(|lookup| lookup.account_key).collect::<Vec<_>>();
+        self.prefetch_lookup_tables(&tables).await?;
+
+        let mut writable = vec![];
+        let mut readonly = vec![];
+        for lookup in lookups.iter() { 
+            let mut addresses = self.get_lookup_table(&lookup.account_key).await?;
+            // tables can be extended after we cached them
+            let max_index = lookup.writable_indexes.iter().chain(lookup.readonly_indexes.iter()).max();
+            if max_index.map(|i| *i as usize >= addresses.len()).unwrap_or(false) { 
+                addresses = self.refresh_lookup_table(&lookup.account_key).await?;
+            }
+
+            let lookup_address = |i: &u8| { 
+                addresses.get(*i as usize).copied().ok_or_else(|| { 
+                    LightNodeError::AddressLookupTable(format!("index {} out of range for table {}", i, lookup.account_key))
+                })
+            };
+            for i in lookup.writable_indexes.iter() { 
+                writable.push(lookup_address(i)?);
+            }
+            for i in lookup.readonly_indexes.iter() { 
+                readonly.push(lookup_address(i)?);
+            }
+        }
+
+        let mut account_keys = msg.static_account_keys().to_vec();
+        account_keys.extend(writable);
+        account_keys.extend(readonly);
+        Ok(Cow::Owned(account_keys))
+    }
+}
@@ -1,248 +1,17 @@
-use std::{str::FromStr, collections::HashMap, path::Path, fs::File, io::{Read, Write}, thread::sleep, time::Duration};
+use std::{path::{Path, PathBuf}, fs::File, io::Read, net::SocketAddr, process::ExitCode, sync::Arc, time::Duration};
 
-use serde::{Serialize, Deserialize};
-use solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
-use solana_sdk::{vote::{instruction::VoteInstruction, self}, signature::{Signature, Keypair}, transaction::{VersionedTransaction, SanitizedTransaction, Transaction}, pubkey::Pubkey, signer::Signer, system_instruction::{transfer, self}, commitment_config::CommitmentConfig};
-use solana_transaction_status::{EncodedTransaction, UiTransactionEncoding, UiConfirmedBlock, EncodedConfirmedBlock, TransactionBinaryEncoding, BlockHeader, EncodedConfirmedTransactionWithStatusMeta, EntryProof, PartialEntry};
-use solana_account_decoder::{self, UiAccountData, parse_stake::{parse_stake, StakeAccountType}, parse_vote::parse_vote};
-use solana_entry::{entry::{Entry, EntrySlice, hash_transactions, next_hash}, poh::Poh};
-use solana_sdk::hash::Hash;
-use solana_sdk::hash::hashv;
-use solana_merkle_tree::{MerkleTree, merkle_tree::SolidProof};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use solana_sdk::{signature::{Signature, Keypair}, transaction::Transaction, signer::Signer, system_instruction};
 
-// from merkle-tree crate
-const LEAF_PREFIX: &[u8] = &[0];
-macro_rules! hash_leaf {
-    {$d:ident} => {
-        hashv(&[LEAF_PREFIX, $d])
-    }
-}
-
-#[macro_export]
-macro_rules! send_rpc_call {
-    ($url:expr, $body:expr) => {{
-        use reqwest::header::{ACCEPT, CONTENT_TYPE};
-        let req_client = reqwest::Client::new();
-
-        let res = req_client
-            .post($url)
-            .body($body)
-            .header(CONTENT_TYPE, "application/json")
-            .header(ACCEPT, "application/json")
-            .send()
-            .await
-            .expect("error")
-            .text()
-            .await
-            .expect("error");
-        res
-    }};
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetBlockResponse {
-    pub jsonrpc: String,
-    pub result: UiConfirmedBlock,
-    pub id: i64,
-}
-
-async fn get_block(slot: u64, endpoint: &String) -> GetBlockResponse { 
-    let mut block_resp = None;
-    loop { 
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "getBlock",
-            "params":[
-                slot,
-                { 
-                    "encoding": "base58", // better for deserialzing
-                    "maxSupportedTransactionVersion": 0,
-                }
-            ]
-        }).to_string();
-        let resp = send_rpc_call!(endpoint, request);
-        let parsed_resp = serde_json::from_str::<GetBlockResponse>(&resp);
-        if parsed_resp.is_err() {  // block is not available yet
-            print!(".");
-            std::io::stdout().flush().unwrap();
-            sleep(Duration::from_millis(500));
-            continue;
-        }
-        block_resp = Some(parsed_resp.unwrap());
-        break;
-    }
-
-    block_resp.unwrap()
-}
-
-async fn parse_block_votes(target_slot: u64, slots_ahead: u64, endpoint: String) -> Option<(u64, HashMap<Hash, u64>)> {
-    // let endpoint = "https://rpc.helius.xyz/?api-key=cee342ba-0773-41f7-a6e0-9ff01fff124b";
-    let vote_program_id = "Vote111111111111111111111111111111111111111".to_string();
-    let vote_program_id = Pubkey::from_str(&vote_program_id).unwrap();
-
-    let client = RpcClient::new(endpoint.clone());
-    let vote_accounts = client.get_vote_accounts().unwrap();
-    let leader_stakes = vote_accounts.current
-        .iter()
-        .chain(vote_accounts.delinquent.iter())
-        .map(|x| (x.node_pubkey.clone(), x.activated_stake))
-        .collect::<HashMap<_, _>>();
-    let total_stake = leader_stakes.values().sum::<u64>();
-
-    let mut votes = HashMap::new();
-
-    for i in 0..slots_ahead {
-        let slot = target_slot + i;
-
-        println!("requesting block @ slot {}", slot);
-        let resp = get_block(slot, &endpoint).await;
-        let block = resp.result;
-    
-        if block.transactions.is_none() { 
-            println!("no transactions");
-            return None;
-        }
-    
-        for tx in block.transactions.unwrap().iter() {
-            let tx = &tx.transaction;
-            let tx = match tx { 
-                EncodedTransaction::Binary(tx, enc) => {
-                    assert!(*enc == TransactionBinaryEncoding::Base58);
-                    let tx = bs58::decode(tx).into_vec().unwrap();
-                    let tx: VersionedTransaction = bincode::deserialize(&tx[..]).unwrap();
-                    tx
-                }
-                _ => panic!("ahh")
-            };
-    
-            let msg = tx.message;
-            if !msg.static_account_keys().contains(&vote_program_id) { 
-                // println!("tx doesnt include vote program ...");
-                continue;
-            }
-    
-            let ix = msg.instructions().get(0).unwrap();
-            let data = &ix.data;
-            let vote_ix: VoteInstruction = bincode::deserialize(&data[..]).unwrap();
-            let bank_hash = match &vote_ix { 
-                VoteInstruction::Vote(v) => Some(v.hash),   
-                VoteInstruction::CompactUpdateVoteState(v) => Some(v.hash),
-                _ => None
-            };
-            if bank_hash.is_none() { continue; }
-            let bank_hash = bank_hash.unwrap();
-
-            // let slot_vote = vote_ix.last_voted_slot().unwrap_or_default();
-            // println!("{:?}", vote_ix);
-            // println!("voted for slot {:?} with bank_hash {:?}", slot_vote, bank_hash);
-            // println!("{:?} {:?}", node_pubkey, stake_amount);
-    
-            // verify the signature
-            let msg_bytes = msg.serialize();
-            let sig_verifies = tx.signatures
-                .iter()
-                .zip(msg.static_account_keys().iter())
-                .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &msg_bytes[..]))
-                .all(|x| x);
-
-            if sig_verifies { 
-                let node_pubkey = msg.static_account_keys().get(0).unwrap().to_string();
-                let stake_amount = leader_stakes.get(&node_pubkey).unwrap();
-
-                let entry = votes.entry(bank_hash).or_insert(0);
-                *entry += stake_amount; 
-            }
-        }
-    }
-
-    Some((total_stake, votes))
-}
-
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetBlockHeadersResponse {
-    pub jsonrpc: String,
-    pub result: Vec<u8>,
-    pub id: i64,
-}
-
-async fn get_block_headers(slot: u64, signature: Signature, endpoint: String) -> GetBlockHeadersResponse { 
-    let request = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "getBlockHeaders",
-        "params":[
-            slot, 
-            signature.as_ref(),
-        ]
-    }).to_string();
-    let resp = send_rpc_call!(endpoint, request);
-    let parsed_resp = serde_json::from_str::<GetBlockHeadersResponse>(&resp);
-    if parsed_resp.is_err() { 
-        println!("ERR: {:?}", resp);
-    }
-    let parsed_resp = parsed_resp.unwrap();
-
-    parsed_resp
-}
-
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GetTransactionResponse {
-    pub jsonrpc: String,
-    pub result: EncodedConfirmedTransactionWithStatusMeta,
-    pub id: i64,
-}
-
-async fn get_tx(signtaure: Signature, endpoint: String) -> GetTransactionResponse { 
-    let mut tx_resp = None;
-
-    while tx_resp.is_none() { 
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "getTransaction",
-            "params": [signtaure.to_string(),
-            {
-                "commitment": "confirmed",
-                "encoding": "json",
-            }]
-        }).to_string();
-        let resp = send_rpc_call!(&endpoint, request);
-        let parsed_resp = serde_json::from_str::<GetTransactionResponse>(&resp);
-        if parsed_resp.is_err() {  // tx is not available yet
-            print!(".");
-            sleep(Duration::from_millis(500));
-            continue;
-        }
-
-        tx_resp = Some(parsed_resp.unwrap());
-    }
-    print!("\n");
-
-    tx_resp.unwrap()
-}
-
-pub fn next_hash_with_tx_hash(
-    start_hash: &Hash,
-    num_hashes: u64,
-    transaction_hash: Option<Hash>,
-) -> Hash {
-    if num_hashes == 0 && transaction_hash.is_none() {
-        return *start_hash;
-    }
-
-    let mut poh = Poh::new(*start_hash, None);
-    poh.hash(num_hashes.saturating_sub(1));
-    if transaction_hash.is_none() {
-        poh.tick().unwrap().hash
-    } else {
-        poh.record(transaction_hash.unwrap()).unwrap().hash
-    }
-}
+use lightnode::{LightClient, LightNodeConfig};
+use lightnode::error::LightNodeError;
+use lightnode::failover::FailoverTransport;
+use lightnode::leader_schedule::LeaderScheduleCheck;
+use lightnode::participation::ParticipationReport;
+use lightnode::stake::Threshold;
+use lightnode::verify::{RangeVerification, VerificationResult, VerificationSummary, stake_pct};
+use lightnode::vote::ParsedVote;
 
 pub fn read_keypair_file<F: AsRef<Path>>(path: F) -> Keypair {
     let mut file = File::open(path.as_ref()).unwrap();
@@ -252,13 +21,13 @@ pub fn read_keypair_file<F: AsRef<Path>>(path: F) -> Keypair {
     Keypair::from_bytes(&bytes[..]).unwrap()
 }
 
-pub async fn verify_slot() { 
-    let endpoint = "http://127.0.0.1:8002";
-    let client = RpcClient::new(endpoint);
+// sends a simple transfer on the local cluster so we have a tx to verify
+async fn send_demo_transfer(light_client: &LightClient<FailoverTransport>) -> Result<(u64, Signature), LightNodeError> { 
+    let client = &light_client.rpc_client;
 
     let path = "./solana/validator/ledger/node1/validator_id.json";
     let keypair = read_keypair_file(path);
-    let balance = client.get_balance(&keypair.pubkey()).unwrap();
+    let balance = client.get_balance(&keypair.pubkey()).await?;
     println!("keypair balance: {:?}", balance);
 
     let path = "./solana/validator/ledger/rando_keys/1.json";
@@ -266,8 +35,8 @@ pub async fn verify_slot() {
     let mut balance = 0;
     // sometimes takes a while to get the balance from airdrop
     while balance == 0 { 
-        balance = client.get_balance(&random.pubkey()).unwrap();
-        sleep(Duration::from_millis(500));
+        balance = client.get_balance(&random.pubkey()).await?;
+        tokio::time::sleep(Duration::from_millis(500)).await;
     }
     println!("random keypair balance: {:?}", balance);
 
@@ -277,105 +46,375 @@ pub async fn verify_slot() {
         &random.pubkey(), 
         100
     );
-    let recent_blockhash = client.get_latest_blockhash().expect("Failed to get latest blockhash.");
+    let recent_blockhash = client.get_latest_blockhash().await?;
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&keypair.pubkey()), &[&keypair], recent_blockhash);
-    let tx_sig = client.send_transaction(&tx).unwrap();
-    let tx_info = get_tx(tx_sig, endpoint.to_string()).await; 
-    let slot = tx_info.result.slot;
-    println!("verifying slot {:?}", slot);
+    let tx_sig = client.send_transaction(&tx).await?;
+    let tx_info = light_client.get_tx(tx_sig).await?; 
+    let slot = tx_info.slot;
 
-    // get headers
-    let block_headers = get_block_headers(slot, tx_sig, endpoint.to_string()).await.result;
-    let block_headers: BlockHeader = bincode::deserialize(&block_headers).unwrap();
-    let entries = block_headers.entries; 
+    Ok((slot, tx_sig))
+}
 
-    // find and verify tx signature in entry
-    let mut tx_found = false;
-    for entry in entries.iter() {
-        match entry { 
-            EntryProof::MerkleEntry(x) => {
-                println!("{:?}", x);
+#[derive(Parser)]
+#[command(name = "lightnode", about = "verifies slots, txs and votes against a solana rpc")]
+struct Cli { 
+    /// rpc endpoint, a comma separated list fails over in order (defaults to `LIGHTNODE_ENDPOINT`)
+    #[arg(long, global = true)]
+    endpoint: Option<String>,
+    #[arg(long, global = true, value_enum, default_value_t = Output::Text)]
+    output: Output,
+    /// also check the blocks were produced by their scheduled leader
+    #[arg(long, global = true)]
+    verify_leader: bool,
+    /// fraction of the stake which has to vote on a slot's bank hash, eg `0.8` or `2/3`
+    #[arg(long, global = true, default_value = "2/3", value_parser = parse_threshold)]
+    threshold: Threshold,
+    /// with no command a demo transfer is sent on the local cluster and verified
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-                // verify merkle proof here 
-                let leaf = tx_sig.as_ref();
-                let candidate = hash_leaf!(leaf);
-                // when len == 1 this does nothing
-                let verified = x.proof.verify(candidate);
-                if !verified { 
-                    println!("tx signature not verified!");
-                    return;
-                }
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output { 
+    Text,
+    // a json object per line
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command { 
+    /// verifies the slot's PoH, bank hash and votes through `tx` (the block's first tx by default),
+    /// a few slots behind the latest one when no slot is given
+    VerifySlot { 
+        slot: Option<u64>,
+        tx: Option<Signature>,
+    },
+    /// verifies every block in `start..=end` and prints a line per slot and a summary
+    VerifyRange { 
+        start: u64,
+        end: u64,
+        /// blocks verified at a time
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+    /// prints the votes parsed from the block at the slot
+    Votes { 
+        slot: u64,
+    },
+    /// verifies new slots as theyre produced
+    Watch { 
+        /// verify every confirmed block (with `blockSubscribe`) instead of skipping to the latest slot
+        #[arg(long)]
+        blocks: bool,
+        /// saves the highest verified slot (and the votes seen, on shutdown) here and resumes from it on restart
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+    },
+    /// recomputes the epoch's leader schedule from the stakes and checks it against the rpc's (the current epoch by default)
+    LeaderSchedule { 
+        epoch: Option<u64>,
+    },
+    /// prints how many of the blocks in `start..=end` each validator voted on and the staked ones which were silent
+    Participation { 
+        start: u64,
+        end: u64,
+    },
+    /// serves the verification results over http (`/health`, `/slot/<n>/verify`, `/slot/<n>/confirmation`)
+    Serve { 
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+    },
+}
+
+// pubkeys and hashes are printed base58 instead of as byte arrays
+#[derive(Serialize)]
+struct VoteOutput { 
+    vote_account: String,
+    node_pubkey: Option<String>,
+    slot: u64,
+    bank_hash: String,
+    stake: u64,
+    signature_verified: bool,
+}
+
+#[derive(Serialize)]
+struct RangeOutput { 
+    start_slot: u64,
+    end_slot: u64,
+    num_blocks: usize,
+    num_verified: usize,
+    elapsed_secs: f64,
+    slots: Vec<VerificationSummary>,
+    errors: Vec<ErrorOutput>,
+}
+
+#[derive(Serialize)]
+struct ValidatorOutput { 
+    vote_account: String,
+    node_pubkey: String,
+    stake: u64,
+    slots_voted: u64,
+    opportunities: u64,
+}
+
+#[derive(Serialize)]
+struct ParticipationOutput { 
+    start_slot: u64,
+    end_slot: u64,
+    num_blocks: u64,
+    validators: Vec<ValidatorOutput>,
+}
+
+#[derive(Serialize)]
+struct VotesOutput { 
+    slot: u64,
+    votes: Vec<VoteOutput>,
+}
+
+#[derive(Serialize)]
+struct ErrorOutput { 
+    slot: Option<u64>,
+    error: String,
+}
+
+fn parse_threshold(s: &str) -> Result<Threshold, String> { 
+    s.parse().map_err(|e: LightNodeError| e.to_string())
+}
 
-                tx_found = true;
-                println!("tx signature verified!");
-                break;
-            }, 
-            _ => {}
-        };
+fn print_json<T: Serialize>(value: &T) { 
+    println!("{}", serde_json::to_string(value).unwrap());
+}
+
+fn print_votes(slot: u64, votes: &[ParsedVote], output: Output) -> ExitCode { 
+    if output == Output::Json { 
+        let votes = votes.iter().map(|vote| VoteOutput { 
+            vote_account: vote.vote_account.to_string(),
+            node_pubkey: vote.node_pubkey.map(|pubkey| pubkey.to_string()),
+            slot: vote.slot,
+            bank_hash: vote.bank_hash.to_string(),
+            stake: vote.stake,
+            signature_verified: vote.signature_verified,
+        }).collect();
+        print_json(&VotesOutput { slot, votes });
+        return ExitCode::SUCCESS;
     }
-    if !tx_found { 
-        println!("tx signature not found in entries...");
-        return;
+    for vote in votes.iter() { 
+        println!(
+            "{:?} (vote account {:?}) voted for slot {:?} with bank_hash {:?} stake {:?} sig verified {:?}",
+            vote.node_pubkey, vote.vote_account, vote.slot, vote.bank_hash, vote.stake, vote.signature_verified
+        );
     }
+    println!("{:?} votes", votes.len());
+    ExitCode::SUCCESS
+}
 
-    // verify the entries are valid PoH ticks / path 
-    let start_blockhash = block_headers.start_blockhash;
-    let genesis = [EntryProof::PartialEntry(PartialEntry {
-        num_hashes: 0,
-        hash: start_blockhash,
-        transaction_hash: None
-    })];
-    let mut entry_pairs = genesis.iter().chain(entries.iter()).zip(entries.iter());
-    let verified = entry_pairs.all(|(x0, x1)| {
-        let start_hash = x0.hash();
-        let r = match x1 { 
-            EntryProof::PartialEntry(x) => {
-                next_hash_with_tx_hash(&start_hash, x.num_hashes, x.transaction_hash) == x.hash
-            }, 
-            EntryProof::MerkleEntry(x) => {
-                let tx_hash = if let Some(hash) = x.proof.root() {
-                    hash
-                } else { 
-                    let tx_sig_ref = tx_sig.as_ref();
-                    hash_leaf!(tx_sig_ref)
-                };
-                next_hash_with_tx_hash(&start_hash, x.num_hashes, Some(tx_hash)) == x.hash
-            }
-        };
-        r
-    });
-    if !verified { 
-        println!("entry verification failed ...");
-        return;
+// the exit code is a failure when the slot didnt verify
+fn print_result(result: &VerificationResult, output: Output) -> ExitCode { 
+    let exit_code = if result.verified { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+    if output == Output::Json { 
+        print_json(&result.summary());
+        return exit_code;
+    }
+    println!("slot: {:?}", result.slot);
+    println!("entries: {:?} ({:?} with txs)", result.num_entries, result.num_tx_entries);
+    if let Some(last_blockhash) = result.last_blockhash { 
+        println!("last blockhash: {:?}", last_blockhash);
+    }
+    if let Some(bank_hash) = result.bank_hash { 
+        println!("bank hash: {:?}", bank_hash);
     }
-    println!("entry verification passed!");
+    println!("bankhash vote stakes: {:?} total stakes: {:?}", result.voted_stake, result.total_stake);
+    match &result.failure_reason { 
+        None => println!("slot verified!"),
+        Some(reason) => println!("slot verification failed: {}", reason),
+    }
+    exit_code
+}
 
-    // recompute the bank hash 
-    let last_blockhash = entries.last().unwrap().hash();
-    let bankhash = hashv(&[
-        block_headers.parent_hash.as_ref(),
-        block_headers.accounts_delta_hash.as_ref(),
-        block_headers.signature_count_buf.as_ref(), 
-        last_blockhash.as_ref()
-    ]);
-    println!("bank hash: {:?}", bankhash);
+fn print_leader_schedule_check(check: &LeaderScheduleCheck, output: Output) -> ExitCode { 
+    match output { 
+        Output::Json => print_json(check),
+        Output::Text if check.verified() => println!(
+            "OK: leader schedule of epoch {} matches the stakes of epoch {} ({} slots)", check.epoch, check.stakes_epoch, check.num_slots
+        ),
+        Output::Text => println!(
+            "ERR: leader schedule of epoch {} differs in {} of {} slots: {:?}",
+            check.epoch, check.mismatched_slots.len(), check.num_slots, check.mismatched_slots
+        ),
+    }
+    if check.verified() { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
 
-    println!("parsing votes from block ...");
-    let vote_result = parse_block_votes(slot, 5, endpoint.to_string()).await;
-    if vote_result.is_none() { 
-        println!("vote verification failed ...");
+// the exit code is a failure when any block in the range failed or errored
+fn print_range(range: &RangeVerification, output: Output) -> ExitCode { 
+    let exit_code = if range.failed().count() == 0 && range.errors.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+    if output == Output::Json { 
+        print_json(&RangeOutput { 
+            start_slot: range.start_slot,
+            end_slot: range.end_slot,
+            num_blocks: range.num_blocks,
+            num_verified: range.num_verified(),
+            elapsed_secs: range.elapsed.as_secs_f64(),
+            slots: range.results.iter().map(|result| result.summary()).collect(),
+            errors: range.errors.iter().map(|(slot, e)| ErrorOutput { slot: Some(*slot), error: e.to_string() }).collect(),
+        });
+        return exit_code;
     }
-    let (total_stake, votes) = vote_result.unwrap();
-    let bankhash_vote_stakes = votes.get(&bankhash).unwrap();
-    println!("bankhash vote stakes: {:?} total stakes: {total_stake:?}", bankhash_vote_stakes);
+    println!("{:>12}  {:>8}  {:>7}  reason", "slot", "verified", "stake%");
+    for result in range.results.iter() { 
+        let summary = result.summary();
+        println!(
+            "{:>12}  {:>8}  {:>7.2}  {}",
+            summary.slot, summary.verified, summary.confirmed_stake_pct, summary.failure_reason.unwrap_or_default()
+        );
+    }
+    for (slot, e) in range.errors.iter() { 
+        println!("{:>12}  {:>8}  {:>7}  {}", slot, "error", "-", e);
+    }
+    println!(
+        "{} blocks in slots {}..={}: {} verified, {} failed, {} errors in {:.1}s",
+        range.num_blocks, range.start_slot, range.end_slot, range.num_verified(), range.failed().count(), range.errors.len(),
+        range.elapsed.as_secs_f64()
+    );
+    exit_code
+}
+
+fn print_participation(report: &ParticipationReport, output: Output) -> ExitCode { 
+    match output { 
+        Output::Json => { 
+            let validators = report.validators.iter().map(|x| ValidatorOutput { 
+                vote_account: x.vote_account.to_string(),
+                node_pubkey: x.node_pubkey.to_string(),
+                stake: x.stake,
+                slots_voted: x.slots_voted,
+                opportunities: x.opportunities,
+            }).collect();
+            print_json(&ParticipationOutput { 
+                start_slot: report.start_slot,
+                end_slot: report.end_slot,
+                num_blocks: report.num_blocks,
+                validators,
+            });
+        }
+        Output::Text => { 
+            let silent = report.silent();
+            println!("{} blocks in slots {}..={}, {} of {} validators silent",
+                report.num_blocks, report.start_slot, report.end_slot, silent.len(), report.validators.len());
+            for x in silent { 
+                println!("  {} (node {}): {:.2}% of the stake", x.vote_account, x.node_pubkey, stake_pct(x.stake as u128, report.total_stake));
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
 
-    // bankhash_vote_stakes >= 2/3 * total_stake
-    // 3 * bankhash_vote_stakes >= 2 * total_stake
-    let is_supermajority = 3 * bankhash_vote_stakes >= 2 * total_stake;
-    println!("bankhash has supermajority of votes: {:?}", is_supermajority);
+fn print_error(slot: Option<u64>, e: &LightNodeError, output: Output) -> ExitCode { 
+    match (output, slot) { 
+        (Output::Json, _) => print_json(&ErrorOutput { slot, error: e.to_string() }),
+        (Output::Text, Some(slot)) => println!("ERR: slot {}: {:?}", slot, e),
+        (Output::Text, None) => println!("ERR: {:?}", e),
+    }
+    if output == Output::Text && e.is_retryable() { 
+        println!("(the rpc doesnt have it yet, try again in a bit)");
+    }
+    ExitCode::FAILURE
 }
 
 #[tokio::main]
-async fn main() {
-    verify_slot().await;
+async fn main() -> ExitCode {
+    // RUST_LOG=info prints a line per verified slot, RUST_LOG=debug shows eg, the rate limiter delays
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+    let cli = Cli::parse();
+    let mut config = match &cli.endpoint { 
+        Some(endpoints) => LightNodeConfig::from_endpoints(endpoints),
+        None => LightNodeConfig::from_env(),
+    };
+    config.verify_leader = cli.verify_leader;
+    config.confirmation_threshold = cli.threshold;
+    let client = LightClient::with_failover(&config);
+    let output = cli.output;
+    let result = match cli.command { 
+        Some(Command::VerifySlot { slot: Some(slot), tx: Some(tx_sig) }) => client.verify_slot(slot, tx_sig).await,
+        // clap only takes a tx after a slot
+        Some(Command::VerifySlot { slot, tx: _ }) => client.verify(slot).await,
+        Some(Command::Votes { slot }) => { 
+            return match client.parse_block_votes(slot, 1).await { 
+                Ok(votes) => print_votes(slot, &votes, output),
+                Err(e) => print_error(Some(slot), &e, output),
+            };
+        }
+        Some(Command::Watch { blocks, state_dir }) => { 
+            // the watcher keeps going past failed slots (theyre only printed) until its shut down, then exits 0
+            // unless the state couldnt be saved
+            let on_result = |slot: u64, result: Result<VerificationResult, LightNodeError>| { 
+                let _ = match result { 
+                    Ok(result) => print_result(&result, output),
+                    Err(e) => print_error(Some(slot), &e, output),
+                };
+            };
+            // ctrl-c lets the slot being verified finish before the watcher returns
+            let (shutdown_sender, shutdown) = tokio::sync::watch::channel(false);
+            tokio::spawn(async move { 
+                if tokio::signal::ctrl_c().await.is_ok() { 
+                    tracing::info!("shutting down");
+                    let _ = shutdown_sender.send(true);
+                }
+            });
+            return match (&state_dir, blocks) { 
+                (Some(dir), _) => match client.watch_with_state(blocks, dir, shutdown, on_result).await { 
+                    Ok(_) => ExitCode::SUCCESS,
+                    Err(e) => print_error(None, &e, output),
+                },
+                (None, true) => { 
+                    client.watch_and_verify_until(None, shutdown, on_result).await;
+                    ExitCode::SUCCESS
+                }
+                (None, false) => { 
+                    client.watch_slots_until(None, shutdown, on_result).await;
+                    ExitCode::SUCCESS
+                }
+            };
+        }
+        Some(Command::LeaderSchedule { epoch }) => { 
+            let check = match epoch { 
+                Some(epoch) => client.verify_leader_schedule(epoch).await,
+                None => match client.leader_stakes().await { 
+                    Ok(stakes) => client.verify_leader_schedule(stakes.epoch).await,
+                    Err(e) => Err(e),
+                },
+            };
+            return match check { 
+                Ok(check) => print_leader_schedule_check(&check, output),
+                Err(e) => print_error(None, &e, output),
+            };
+        }
+        Some(Command::VerifyRange { start, end, concurrency }) => { 
+            return match client.verify_range(start, end, concurrency).await { 
+                Ok(range) => print_range(&range, output),
+                Err(e) => print_error(None, &e, output),
+            };
+        }
+        Some(Command::Participation { start, end }) => { 
+            return match client.participation(start, end).await { 
+                Ok(report) => print_participation(&report, output),
+                Err(e) => print_error(None, &e, output),
+            };
+        }
+        Some(Command::Serve { listen }) => { 
+            return match lightnode::server::serve(Arc::new(client), listen).await { 
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => print_error(None, &e, output),
+            };
+        }
+        None => match send_demo_transfer(&client).await { 
+            Ok((slot, tx_sig)) => client.verify_slot(slot, tx_sig).await,
+            Err(e) => Err(e),
+        },
+    };
+    match result { 
+        Ok(result) => print_result(&result, output),
+        Err(e) => print_error(None, &e, output),
+    }
 }
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use prometheus::{Encoder, Gauge, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+use crate::error::LightNodeError;
+use crate::verify::{VerificationResult, stake_pct};
+
+/// prometheus metrics of a client, served on `/metrics` by the http api
+pub struct Metrics { 
+    registry: Registry,
+    pub slots_verified: IntCounter,
+    // by what failed (eg, poh, supermajority) or the error when verifying errored out
+    pub verification_failures: IntCounterVec,
+    // voted stake on the bank hash of the latest slot verified
+    pub confirmed_stake_pct: Gauge,
+    pub verification_latency: Histogram,
+    pub votes_parsed: IntCounter,
+    pub invalid_vote_signatures: IntCounter,
+    pub suspicious_votes: IntCounter,
+    // vote txs which landed with an error (eg, a vote too old), theyre not parsed
+    pub failed_votes: IntCounter,
+}
+
+// the error label of a `verify_slot` which didnt return a result
+fn error_kind(e: &LightNodeError) -> &'static str { 
+    match e { 
+        LightNodeError::Divergence(_) => "divergence",
+        LightNodeError::HistoricalStakesUnavailable { .. } => "historical_stakes",
+        LightNodeError::Timeout(_) => "timeout",
+        _ => "rpc_error",
+    }
+}
+
+impl Metrics { 
+    pub fn new() -> Self { 
+        let registry = Registry::new();
+        // the names are all static so none of these can fail
+        let slots_verified = IntCounter::new("lightnode_slots_verified_total", "slots which passed verification").unwrap();
+        let verification_failures = IntCounterVec::new(
+            Opts::new("lightnode_verification_failures_total", "slots which failed verification"),
+            &["kind"],
+        ).unwrap();
+        let confirmed_stake_pct = Gauge::new("lightnode_confirmed_stake_pct", "stake which voted on the latest verified slot's bank hash").unwrap();
+        let verification_latency = Histogram::with_opts(
+            HistogramOpts::new("lightnode_verification_latency_seconds", "time taken to verify a slot")
+                .buckets(vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+        ).unwrap();
+        let votes_parsed = IntCounter::new("lightnode_votes_parsed_total", "votes parsed out of blocks").unwrap();
+        let invalid_vote_signatures = IntCounter::new("lightnode_invalid_vote_signatures_total", "block votes with an invalid signature").unwrap();
+        let suspicious_votes = IntCounter::new("lightnode_suspicious_votes_total", "block votes which contradict the slot hashes").unwrap();
+        let failed_votes = IntCounter::new("lightnode_failed_votes_total", "block vote txs which failed").unwrap();
+
+        registry.register(Box::new(slots_verified.clone())).unwrap();
+        registry.register(Box::new(verification_failures.clone())).unwrap();
+        registry.register(Box::new(confirmed_stake_pct.clone())).unwrap();
+        registry.register(Box::new(verification_latency.clone())).unwrap();
+        registry.register(Box::new(votes_parsed.clone())).unwrap();
+        registry.register(Box::new(invalid_vote_signatures.clone())).unwrap();
+        registry.register(Box::new(suspicious_votes.clone())).unwrap();
+        registry.register(Box::new(failed_votes.clone())).unwrap();
+
+        Metrics { 
+            registry,
+            slots_verified,
+            verification_failures,
+            confirmed_stake_pct,
+            verification_latency,
+            votes_parsed,
+            invalid_vote_signatures,
+            suspicious_votes,
+            failed_votes,
+        }
+    }
+
+    pub fn record_verification(&self, result: &Result<VerificationResult, LightNodeError>, elapsed: Duration) { 
+        self.verification_latency.observe(elapsed.as_secs_f64());
+        match result { 
+            Ok(result) => { 
+                if result.total_stake > 0 { 
+                    self.confirmed_stake_pct.set(stake_pct(result.voted_stake, result.total_stake));
+                }
+                match result.failure_kind() { 
+                    None => self.slots_verified.inc(),
+                    Some(kind) => self.verification_failures.with_label_values(&[kind]).inc(),
+                }
+            }
+            Err(e) => self.verification_failures.with_label_values(&[error_kind(e)]).inc(),
+        }
+    }
+
+    /// the metrics in the prometheus text format
+    pub fn encode(&self) -> String { 
+        let mut buf = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+impl Default for Metrics { 
+    fn default() -> Self { 
+        Metrics::new()
+    }
+}
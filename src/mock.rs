@@ -0,0 +1,95 @@
+use std::{collections::{HashMap, VecDeque}, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::transport::{RpcTransport, TransportError};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// what a `MockTransport` answers a request with
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockResponse { 
+    // the JSON-RPC `result`
+    Result(Value),
+    // a JSON-RPC error object
+    Error { code: i64, message: String },
+    // http 429
+    RateLimited(Option<Duration>),
+    // http 5xx
+    Status(u16),
+    // never answers, only the client's request timeout ends it
+    Hang,
+}
+
+/// an rpc in a box: answers each JSON-RPC method with canned responses (eg, a `getBlock` fixture,
+/// errors, 429s or hangs) to drive a `LightClient`'s retry, failover and error paths without a validator
+#[derive(Default)]
+pub struct MockTransport { 
+    // method -> responses, in order (the last one keeps being returned)
+    responses: Mutex<HashMap<String, VecDeque<MockResponse>>>,
+    // every request body received, in order
+    pub requests: Mutex<Vec<String>>,
+}
+
+impl MockTransport { 
+    pub fn new() -> Self { 
+        MockTransport::default()
+    }
+
+    /// queues `response` for the next request of `method` (after the ones already queued)
+    pub fn respond(self, method: &str, response: MockResponse) -> Self { 
+        self.responses.lock().unwrap().entry(method.to_string()).or_default().push_back(response);
+        self
+    }
+
+    /// number of requests (and batched requests) received for `method`
+    pub fn num_requests(&self, method: &str) -> usize { 
+        self.requests.lock().unwrap().iter()
+            .filter_map(|body| serde_json::from_str::<Value>(body).ok())
+            .map(|body| match body { 
+                Value::Array(requests) => requests.iter().filter(|x| x["method"] == method).count(),
+                request => (request["method"] == method) as usize,
+            })
+            .sum()
+    }
+
+    fn next_response(&self, method: &str) -> MockResponse { 
+        let mut responses = self.responses.lock().unwrap();
+        match responses.get_mut(method) { 
+            Some(queue) if queue.len() > 1 => queue.pop_front().unwrap(),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => MockResponse::Error { code: METHOD_NOT_FOUND, message: format!("no mock response for {}", method) },
+        }
+    }
+
+    // the response object of a single request, Err for the responses which arent JSON-RPC
+    fn answer(&self, request: &Value) -> Result<Value, MockResponse> { 
+        let id = request["id"].clone();
+        match self.next_response(request["method"].as_str().unwrap_or_default()) { 
+            MockResponse::Result(result) => Ok(json!({ "jsonrpc": "2.0", "id": id, "result": result })),
+            MockResponse::Error { code, message } => Ok(json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })),
+            response => Err(response),
+        }
+    }
+}
+
+#[async_trait]
+impl RpcTransport for MockTransport { 
+    async fn send(&self, body: String) -> Result<String, TransportError> { 
+        self.requests.lock().unwrap().push(body.clone());
+        let request: Value = serde_json::from_str(&body).map_err(|e| TransportError::Other(e.to_string()))?;
+
+        // a batch fails as a whole when any of its requests get a transport level response
+        let answer = match &request { 
+            Value::Array(requests) => requests.iter().map(|x| self.answer(x)).collect::<Result<Vec<_>, _>>().map(Value::Array),
+            request => self.answer(request),
+        };
+        match answer { 
+            Ok(response) => Ok(response.to_string()),
+            Err(MockResponse::RateLimited(retry_after)) => Err(TransportError::RateLimited(retry_after)),
+            Err(MockResponse::Status(status)) => Err(TransportError::Status(status)),
+            Err(_) => futures::future::pending().await,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::stake::{StakeInfo, total_stake};
+use crate::transport::RpcTransport;
+use crate::verify::VOTE_SLOTS_AHEAD;
+use crate::vote::ParsedVote;
+
+/// how many of the produced slots in a range a vote account voted on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorParticipation { 
+    pub vote_account: Pubkey,
+    pub node_pubkey: Pubkey,
+    pub stake: u64,
+    pub slots_voted: u64,
+    // slots in the range with a block, every one of them could have been voted on
+    pub opportunities: u64,
+}
+
+impl ValidatorParticipation { 
+    pub fn participation(&self) -> f64 { 
+        if self.opportunities == 0 { 
+            0.0
+        } else { 
+            self.slots_voted as f64 / self.opportunities as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticipationReport { 
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub num_blocks: u64,
+    pub total_stake: u128,
+    // every staked vote account, most stake first
+    pub validators: Vec<ValidatorParticipation>,
+}
+
+impl ParticipationReport { 
+    /// the staked validators which didnt vote on any block in the range, most stake first
+    /// (delinquent from our point of view even if `getVoteAccounts` doesnt say so yet)
+    pub fn silent(&self) -> Vec<&ValidatorParticipation> { 
+        self.validators.iter()
+            .filter(|x| x.stake > 0 && x.opportunities > 0 && x.slots_voted == 0)
+            .collect()
+    }
+}
+
+/// the participation of every vote account in `stakes` over the `produced_slots`, a vote counts for
+/// its slot and every slot in its tower (voting on a slot also votes on its ancestors)
+pub fn vote_participation(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, produced_slots: &[u64]) -> Vec<ValidatorParticipation> { 
+    let produced_slots = produced_slots.iter().copied().collect::<HashSet<_>>();
+    let mut voted: HashMap<Pubkey, HashSet<u64>> = HashMap::new();
+    for vote in votes.iter().filter(|vote| vote.counts()) { 
+        let slots = voted.entry(vote.vote_account).or_default();
+        let tower_slots = vote.voted_slots.iter().map(|(slot, _)| *slot);
+        for slot in std::iter::once(vote.slot).chain(tower_slots) { 
+            if produced_slots.contains(&slot) { 
+                slots.insert(slot);
+            }
+        }
+    }
+
+    let mut validators = stakes.iter()
+        .map(|(vote_account, stake_info)| ValidatorParticipation { 
+            vote_account: *vote_account,
+            node_pubkey: stake_info.node_pubkey,
+            stake: stake_info.activated_stake,
+            slots_voted: voted.get(vote_account).map(|slots| slots.len() as u64).unwrap_or(0),
+            opportunities: produced_slots.len() as u64,
+        })
+        .collect::<Vec<_>>();
+    validators.sort_by(|a, b| b.stake.cmp(&a.stake).then(a.vote_account.cmp(&b.vote_account)));
+    validators
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// scans the blocks of `start_slot..=end_slot` (and the few after it, where the votes on the last
+    /// slots land) for the votes of every validator, weighted with the stakes of `start_slot`'s epoch
+    pub async fn participation(&self, start_slot: u64, end_slot: u64) -> Result<ParticipationReport, LightNodeError> { 
+        let produced_slots = self.get_confirmed_blocks(start_slot, end_slot).await?;
+        let num_slots = (end_slot + 1).saturating_sub(start_slot);
+        let votes = self.parse_block_votes(start_slot, num_slots + VOTE_SLOTS_AHEAD).await?;
+        let stakes = self.stakes_for_slot(start_slot).await?;
+
+        Ok(ParticipationReport { 
+            start_slot,
+            end_slot,
+            num_blocks: produced_slots.len() as u64,
+            total_stake: total_stake(&stakes.stakes),
+            validators: vote_participation(&votes, &stakes.stakes, &produced_slots),
+        })
+    }
+}
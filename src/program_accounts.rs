@@ -0,0 +1,184 @@
+use futures::{Stream, StreamExt, stream};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_response::RpcKeyedAccount,
+};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, stake::{self, state::Delegation}};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::rpc::{ContextValue, decode_account};
+use crate::transport::RpcTransport;
+
+// layout of a `StakeState::Stake` account: the enum tag then the meta (rent exempt reserve,
+// authorized and lockup) and then the delegation which starts with the vote account
+const STAKE_ACCOUNT_SIZE: u64 = 200;
+const STAKE_STATE_DELEGATED: [u8; 4] = 2u32.to_le_bytes();
+pub const STAKE_VOTER_PUBKEY_OFFSET: usize = 4 + 8 + 64 + 48;
+// voter pubkey, stake, activation and deactivation epochs and the warmup cooldown rate
+const STAKE_DELEGATION_SIZE: usize = 32 + 8 + 8 + 8 + 8;
+// a vote account starts with the version tag and then the node identity
+const VOTE_NODE_PUBKEY_OFFSET: usize = 4;
+
+/// the `filters` of a `getProgramAccounts` call, an account has to match every filter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgramAccountQuery { 
+    pub filters: Vec<RpcFilterType>,
+    // only this part of each account's data is returned (the filters still match the full data)
+    pub data_slice: Option<UiDataSliceConfig>,
+}
+
+impl ProgramAccountQuery { 
+    pub fn new() -> Self { 
+        ProgramAccountQuery::default()
+    }
+
+    pub fn data_size(mut self, size: u64) -> Self { 
+        self.filters.push(RpcFilterType::DataSize(size));
+        self
+    }
+
+    pub fn memcmp(mut self, offset: usize, bytes: Vec<u8>) -> Self { 
+        self.filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes)));
+        self
+    }
+
+    pub fn data_slice(mut self, offset: usize, length: usize) -> Self { 
+        self.data_slice = Some(UiDataSliceConfig { offset, length });
+        self
+    }
+
+    /// stake accounts with a delegation (initialized and uninitialized ones dont delegate anything)
+    pub fn delegated_stake_accounts() -> Self { 
+        ProgramAccountQuery::new()
+            .data_size(STAKE_ACCOUNT_SIZE)
+            .memcmp(0, STAKE_STATE_DELEGATED.to_vec())
+    }
+
+    /// stake accounts delegated to `vote_account`
+    pub fn stake_accounts_delegated_to(vote_account: &Pubkey) -> Self { 
+        ProgramAccountQuery::delegated_stake_accounts()
+            .memcmp(STAKE_VOTER_PUBKEY_OFFSET, vote_account.to_bytes().to_vec())
+    }
+
+    /// vote accounts of `node_pubkey`, the authorized voters come after the variable length
+    /// votes so they cant be filtered on
+    pub fn vote_accounts_of_node(node_pubkey: &Pubkey) -> Self { 
+        ProgramAccountQuery::new().memcmp(VOTE_NODE_PUBKEY_OFFSET, node_pubkey.to_bytes().to_vec())
+    }
+
+    /// the `filters` param as its sent to the rpc, memcmp bytes are base58 (the encoding every rpc version takes)
+    pub fn to_json(&self) -> serde_json::Value { 
+        self.filters.iter()
+            .map(|filter| match filter { 
+                RpcFilterType::DataSize(size) => serde_json::json!({ "dataSize": size }),
+                RpcFilterType::Memcmp(memcmp) => { 
+                    let bytes = memcmp.bytes().map(|bytes| bs58::encode(bytes.as_slice()).into_string()).unwrap_or_default();
+                    serde_json::json!({ "memcmp": { "offset": memcmp.offset, "bytes": bytes } })
+                }
+                filter => serde_json::json!(filter),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// the accounts of `program_id` which match `filters`, a single `getProgramAccounts` over a large program
+    /// (eg, stake) times out on public rpcs so its split into 256 calls which each only match the accounts
+    /// with a given byte at `partition_offset` (eg, the first byte of a pubkey field)
+    pub fn program_accounts_stream(&self, program_id: Pubkey, query: ProgramAccountQuery, partition_offset: usize) -> impl Stream<Item = Result<(Pubkey, Account), LightNodeError>> + '_ { 
+        stream::iter(0..=u8::MAX)
+            .then(move |byte| { 
+                let query = query.clone().memcmp(partition_offset, vec![byte]);
+                async move { 
+                    // a partition which fails on the way (eg, times out) is retried on its own,
+                    // any other error is returned right away
+                    let mut attempt = 1;
+                    loop { 
+                        match self.get_program_accounts_with_query(&program_id, &query).await { 
+                            Err(e) if e.is_transient() && attempt < self.retry_policy.max_attempts => { 
+                                tracing::debug!("getProgramAccounts partition {} failed (attempt {}): {}", byte, attempt, e);
+                                tokio::time::sleep(self.retry_policy.delay_for_attempt(attempt)).await;
+                                attempt += 1;
+                            }
+                            accounts => return accounts,
+                        }
+                    }
+                }
+            })
+            .flat_map(|accounts| stream::iter(match accounts { 
+                Ok(accounts) => accounts.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(e) => vec![Err(e)],
+            }))
+    }
+
+    pub async fn get_program_accounts_with_query(&self, program_id: &Pubkey, query: &ProgramAccountQuery) -> Result<Vec<(Pubkey, Account)>, LightNodeError> { 
+        let config = RpcProgramAccountsConfig { 
+            // sent as `to_json` below
+            filters: None,
+            account_config: RpcAccountInfoConfig { 
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: query.data_slice,
+                commitment: Some(CommitmentConfig { commitment: self.commitment }),
+                min_context_slot: None,
+            },
+            with_context: Some(true),
+        };
+        let mut config = serde_json::to_value(config)?;
+        config["filters"] = query.to_json();
+        let params = serde_json::json!([program_id.to_string(), config]);
+        let resp = self.rpc::<_, ContextValue<Vec<RpcKeyedAccount>>>("getProgramAccounts", params).await?;
+        resp.value.iter()
+            .map(|x| { 
+                let pubkey = x.pubkey.parse::<Pubkey>()
+                    .map_err(|e| LightNodeError::InvalidResponse(format!("invalid account pubkey {}: {}", x.pubkey, e)))?;
+                Ok((pubkey, decode_account(&pubkey, &x.account)?))
+            })
+            .collect()
+    }
+
+    /// the delegated stake accounts, partitioned by the first byte of their vote account
+    pub fn stake_accounts_stream(&self) -> impl Stream<Item = Result<(Pubkey, Account), LightNodeError>> + '_ { 
+        self.program_accounts_stream(stake::program::id(), ProgramAccountQuery::delegated_stake_accounts(), STAKE_VOTER_PUBKEY_OFFSET)
+    }
+
+    /// `stake_accounts_stream` but only fetching (and decoding) the delegation of each account,
+    /// about a third of the data
+    pub fn stake_delegations_stream(&self) -> impl Stream<Item = Result<(Pubkey, Delegation), LightNodeError>> + '_ { 
+        let query = ProgramAccountQuery::delegated_stake_accounts()
+            .data_slice(STAKE_VOTER_PUBKEY_OFFSET, STAKE_DELEGATION_SIZE);
+        self.program_accounts_stream(stake::program::id(), query, STAKE_VOTER_PUBKEY_OFFSET)
+            .map(|account| -> Result<(Pubkey, Delegation), LightNodeError> { 
+                let (pubkey, account) = account?;
+                Ok((pubkey, bincode::deserialize(&account.data)?))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests { 
+    use super::*;
+
+    #[test]
+    fn test_query_json() { 
+        let voter = Pubkey::new_unique();
+        let query = ProgramAccountQuery::new()
+            .data_size(200)
+            .memcmp(124, voter.to_bytes().to_vec());
+        let expected = serde_json::json!([
+            { "dataSize": 200 },
+            { "memcmp": { "offset": 124, "bytes": voter.to_string() } },
+        ]);
+        assert_eq!(query.to_json(), expected);
+
+        assert_eq!(ProgramAccountQuery::stake_accounts_delegated_to(&voter).to_json(), serde_json::json!([
+            { "dataSize": 200 },
+            { "memcmp": { "offset": 0, "bytes": bs58::encode([2, 0, 0, 0]).into_string() } },
+            { "memcmp": { "offset": STAKE_VOTER_PUBKEY_OFFSET, "bytes": voter.to_string() } },
+        ]));
+        assert_eq!(ProgramAccountQuery::new().to_json(), serde_json::json!([]));
+    }
+}
@@ -0,0 +1,71 @@
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{BlockHeader, TransactionDetails, UiConfirmedBlock};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::rpc::GetBlockOptions;
+use crate::transport::{HttpTransport, RpcTransport};
+use crate::verify::decode_block_headers;
+
+fn block_summary(block: &UiConfirmedBlock) -> String { 
+    format!("blockhash {} previous blockhash {}", block.blockhash, block.previous_blockhash)
+}
+
+fn block_headers_summary(block_headers: &BlockHeader) -> String { 
+    format!("{} entries, last entry {:?}", block_headers.entries.len(), block_headers.entries.last().map(|x| x.hash()))
+}
+
+// (endpoint, summary) pairs, all of the summaries have to match
+fn check_agreement(seen: Vec<(String, String)>) -> Result<(), LightNodeError> { 
+    if seen.iter().all(|(_, summary)| *summary == seen[0].1) { 
+        Ok(())
+    } else { 
+        Err(LightNodeError::Divergence(seen))
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    fn quorum_transports(&self) -> Result<&[HttpTransport], LightNodeError> { 
+        if self.quorum_transports.len() < self.quorum { 
+            return Err(LightNodeError::Verification(format!(
+                "a quorum of {} needs {} endpoints but only {} are configured", self.quorum, self.quorum, self.quorum_transports.len()
+            )));
+        }
+        Ok(&self.quorum_transports)
+    }
+
+    /// fetches the block from the primary and checks the other quorum endpoints return the same blockhash and
+    /// previous blockhash. each endpoint is asked on its own (no failover) so every sample is attributed to the
+    /// endpoint which answered it, returns the primary's block
+    pub async fn get_block_with_quorum(&self, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        let (primary, peers) = match self.quorum_transports()?.split_first() { 
+            Some(transports) => transports,
+            None => return Err(LightNodeError::Verification("no quorum endpoints".to_string())),
+        };
+        let block = self.get_block_with_options_from(primary, slot, options).await?;
+
+        let mut seen = vec![(primary.endpoint.clone(), block_summary(&block))];
+        // the peers only need to agree on the hashes
+        let peer_options = GetBlockOptions::new(self.encoding).transaction_details(TransactionDetails::None).rewards(false);
+        for peer in peers { 
+            let peer_block = self.get_block_with_options_from(peer, slot, &peer_options).await?;
+            seen.push((peer.endpoint.clone(), block_summary(&peer_block)));
+        }
+        check_agreement(seen)?;
+        Ok(block)
+    }
+
+    /// `get_block_with_quorum` for the block headers, the endpoints have to return the same number of entries
+    /// and last entry hash
+    pub async fn get_block_headers_with_quorum(&self, slot: u64, tx_sig: Signature) -> Result<Vec<u8>, LightNodeError> { 
+        let mut block_headers = None;
+        let mut seen = vec![];
+        for transport in self.quorum_transports()? { 
+            let headers = self.get_block_headers_from(transport, slot, tx_sig).await?;
+            seen.push((transport.endpoint.clone(), block_headers_summary(&decode_block_headers(slot, &headers)?)));
+            block_headers.get_or_insert(headers);
+        }
+        check_agreement(seen)?;
+        block_headers.ok_or_else(|| LightNodeError::Verification("no quorum endpoints".to_string()))
+    }
+}
@@ -0,0 +1,85 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit { 
+    // rate the bucket refills at
+    pub requests_per_second: f64,
+    // max number of requests which can go out back to back
+    pub burst: u32,
+}
+
+struct Bucket { 
+    // can go negative when requests are queued up waiting for a token
+    tokens: f64,
+    last_refill: Instant,
+    // set when the endpoint rate limited us (http 429)
+    cooldown_until: Option<Instant>,
+}
+
+/// token bucket shared by every request a client sends
+pub struct RateLimiter { 
+    limit: Option<RateLimit>,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter { 
+    pub fn new(limit: Option<RateLimit>) -> Self { 
+        let tokens = limit.as_ref().map(|limit| limit.burst.max(1) as f64).unwrap_or(0.0);
+        RateLimiter { 
+            limit,
+            bucket: Mutex::new(Bucket { 
+                tokens,
+                last_refill: Instant::now(),
+                cooldown_until: None,
+            }),
+        }
+    }
+
+    /// only waits out cooldowns from 429s
+    pub fn unlimited() -> Self { 
+        RateLimiter::new(None)
+    }
+
+    /// waits until the next request is allowed to go out
+    pub async fn acquire(&self) { 
+        let delay = self.reserve();
+        if !delay.is_zero() { 
+            tracing::debug!("rate limiter delaying request by {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // takes a token (going into debt when there are none left) and returns how long to wait for it
+    fn reserve(&self) -> Duration { 
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let cooldown = bucket.cooldown_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or(Duration::ZERO);
+
+        let limit = match &self.limit { 
+            Some(limit) => limit,
+            None => return cooldown,
+        };
+        let rate = limit.requests_per_second.max(1e-3);
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(limit.burst.max(1) as f64);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+
+        let delay = if bucket.tokens >= 0.0 { 
+            Duration::ZERO
+        } else { 
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        };
+        delay.max(cooldown)
+    }
+
+    /// holds back every request for at least `duration`
+    pub fn cooldown(&self, duration: Duration) { 
+        tracing::debug!("rate limited by the endpoint, cooling down for {:?}", duration);
+        let until = Instant::now() + duration;
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.cooldown_until = Some(bucket.cooldown_until.map_or(until, |x| x.max(until)));
+    }
+}
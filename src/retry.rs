@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy { 
+    // total number of attempts, including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy { 
+    /// no retries, a single attempt
+    pub fn none() -> Self { 
+        RetryPolicy { max_attempts: 1, ..RetryPolicy::default() }
+    }
+
+    /// exponential backoff capped at `max_delay`, with jitter in [delay/2, delay]
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration { 
+        let exp = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(exp).min(self.max_delay);
+        let half = delay / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter)
+    }
+}
+
+impl Default for RetryPolicy { 
+    fn default() -> Self { 
+        RetryPolicy { 
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
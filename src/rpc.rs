@@ -0,0 +1,513 @@
+use std::{collections::HashMap, str::FromStr, time::{Duration, Instant}};
+
+use futures::{StreamExt, stream};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_response::RpcVoteAccountStatus;
+use solana_sdk::{account::Account, commitment_config::CommitmentLevel, epoch_info::EpochInfo, epoch_schedule::EpochSchedule, hash::Hash, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{UiConfirmedBlock, UiTransactionEncoding, EncodedConfirmedTransactionWithStatusMeta, TransactionDetails};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::retry::RetryPolicy;
+use crate::transport::{RpcTransport, TransportError};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcErrorObject { 
+    pub code: i64,
+    pub message: String,
+}
+
+// JSON-RPC error codes for blocks which can show up later
+const BLOCK_NOT_AVAILABLE: i64 = -32004;
+const BLOCK_STATUS_NOT_AVAILABLE_YET: i64 = -32014;
+// JSON-RPC error codes for slots which will never have a block
+const SLOT_SKIPPED: i64 = -32007;
+const LONG_TERM_STORAGE_SLOT_SKIPPED: i64 = -32009;
+const INVALID_PARAMS: i64 = -32602;
+
+// maps the block specific JSON-RPC errors to their own variants
+fn map_block_error(slot: u64, e: LightNodeError) -> LightNodeError { 
+    match e { 
+        LightNodeError::Rpc { code, .. } if code == BLOCK_NOT_AVAILABLE || code == BLOCK_STATUS_NOT_AVAILABLE_YET => { 
+            LightNodeError::BlockUnavailable(slot)
+        }
+        LightNodeError::Rpc { code, .. } if code == SLOT_SKIPPED || code == LONG_TERM_STORAGE_SLOT_SKIPPED => { 
+            LightNodeError::SlotSkipped(slot)
+        }
+        e => e,
+    }
+}
+
+/// the `{ context, value }` result of the account methods
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ContextValue<R> { 
+    pub context: RpcContext,
+    pub value: R,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RpcContext { 
+    pub slot: u64,
+}
+
+pub(crate) fn decode_account(pubkey: &Pubkey, account: &UiAccount) -> Result<Account, LightNodeError> { 
+    account.decode::<Account>()
+        .ok_or_else(|| LightNodeError::InvalidResponse(format!("undecodable account {}", pubkey)))
+}
+
+/// the envelope of a successful JSON-RPC response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse<R> { 
+    pub jsonrpc: String,
+    pub result: R,
+    pub id: u64,
+}
+
+pub fn parse_rpc_response<T: DeserializeOwned>(resp: &str, id: u64) -> Result<T, LightNodeError> { 
+    let value: serde_json::Value = serde_json::from_str(resp)?;
+    parse_rpc_value(value, id)
+}
+
+pub fn parse_rpc_value<T: DeserializeOwned>(value: serde_json::Value, id: u64) -> Result<T, LightNodeError> { 
+    check_envelope(&value, id)?;
+    if let Some(e) = rpc_error(&value) { 
+        return Err(e);
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// checks the response is JSON-RPC 2.0 and answers the request with `id`
+pub fn check_envelope(value: &serde_json::Value, id: u64) -> Result<(), LightNodeError> { 
+    match value.get("jsonrpc").and_then(|v| v.as_str()) { 
+        Some("2.0") => {},
+        v => return Err(LightNodeError::InvalidResponse(format!("expected jsonrpc 2.0, got {:?}", v))),
+    }
+    match value.get("id") { 
+        Some(resp_id) if resp_id.as_u64() == Some(id) => Ok(()),
+        // requests which couldnt be parsed by the node get an error with a null id
+        Some(serde_json::Value::Null) if value.get("error").is_some() => Ok(()),
+        resp_id => Err(LightNodeError::InvalidResponse(format!("expected response id {}, got {:?}", id, resp_id))),
+    }
+}
+
+fn rpc_error(value: &serde_json::Value) -> Option<LightNodeError> { 
+    let error = value.get("error")?;
+    Some(match serde_json::from_value::<RpcErrorObject>(error.clone()) { 
+        Ok(error) => LightNodeError::Rpc { code: error.code, message: error.message },
+        Err(e) => e.into(),
+    })
+}
+
+fn rpc_request<P: Serialize>(id: u64, method: &str, params: P) -> serde_json::Value { 
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params,
+    })
+}
+
+/// what `getBlock` should include, smaller payloads are faster to fetch and parse
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetBlockOptions { 
+    pub encoding: UiTransactionEncoding,
+    pub transaction_details: TransactionDetails,
+    pub rewards: bool,
+}
+
+impl GetBlockOptions { 
+    /// full transactions and rewards
+    pub fn new(encoding: UiTransactionEncoding) -> Self { 
+        GetBlockOptions { 
+            encoding,
+            transaction_details: TransactionDetails::Full,
+            rewards: true,
+        }
+    }
+
+    pub fn transaction_details(mut self, transaction_details: TransactionDetails) -> Self { 
+        self.transaction_details = transaction_details;
+        self
+    }
+
+    pub fn rewards(mut self, rewards: bool) -> Self { 
+        self.rewards = rewards;
+        self
+    }
+}
+
+/// the chain linking fields of a block, from `get_block_summary`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSummary { 
+    pub blockhash: String,
+    pub previous_blockhash: String,
+    pub parent_slot: u64,
+    // not every node returns these
+    pub block_height: Option<u64>,
+    pub block_time: Option<i64>,
+}
+
+/// the blocks of a slot range from `fetch_block_range`
+#[derive(Debug, Default)]
+pub struct BlockRange { 
+    // in slot order
+    pub blocks: Vec<(u64, UiConfirmedBlock)>,
+    // slots in the range which have no block
+    pub skipped: Vec<u64>,
+    // slots which have a block that couldnt be fetched
+    pub failed: Vec<(u64, LightNodeError)>,
+}
+
+impl BlockRange { 
+    /// slots which produced a block, including the ones which failed to fetch
+    pub fn confirmed_slots(&self) -> Vec<u64> { 
+        let mut slots = self.blocks.iter()
+            .map(|(slot, _)| *slot)
+            .chain(self.failed.iter().map(|(slot, _)| *slot))
+            .collect::<Vec<_>>();
+        slots.sort();
+        slots
+    }
+}
+
+// how many blocks `fetch_block_range` fetches at once
+const MAX_CONCURRENT_BLOCK_FETCHES: usize = 8;
+
+fn get_block_params(slot: u64, options: &GetBlockOptions, commitment: CommitmentLevel) -> serde_json::Value { 
+    serde_json::json!([
+        slot,
+        { 
+            "encoding": options.encoding,
+            "transactionDetails": options.transaction_details,
+            "rewards": options.rewards,
+            "commitment": commitment,
+            "maxSupportedTransactionVersion": 0,
+        }
+    ])
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// `rpc_call` with the client's request timeout and retry policy
+    pub async fn send_request(&self, request: String) -> Result<String, LightNodeError> { 
+        self.rpc_call(request, self.request_timeout, &self.retry_policy).await
+    }
+
+    /// sends the request with `timeout` on every attempt, retrying transient transport failures
+    /// according to `retry_policy` (JSON-RPC error payloads are returned as is and never retried)
+    pub async fn rpc_call(&self, request: String, timeout: Duration, retry_policy: &RetryPolicy) -> Result<String, LightNodeError> { 
+        self.rpc_call_to(&self.transport, request, timeout, retry_policy).await
+    }
+
+    /// `send_request` through a transport other than the client's own (eg, a quorum endpoint)
+    pub(crate) async fn send_request_to<U: RpcTransport>(&self, transport: &U, request: String) -> Result<String, LightNodeError> { 
+        self.rpc_call_to(transport, request, self.request_timeout, &self.retry_policy).await
+    }
+
+    async fn rpc_call_to<U: RpcTransport>(&self, transport: &U, request: String, timeout: Duration, retry_policy: &RetryPolicy) -> Result<String, LightNodeError> { 
+        let max_attempts = retry_policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop { 
+            self.rate_limiter.acquire().await;
+            // the transport can have its own (eg, http) timeout, this also bounds the ones which dont
+            let resp = match tokio::time::timeout(timeout, transport.send(request.clone())).await { 
+                Ok(resp) => resp,
+                Err(_) => Err(TransportError::Timeout(timeout)),
+            };
+            if let Err(TransportError::RateLimited(retry_after)) = &resp { 
+                let cooldown = retry_after.unwrap_or_else(|| retry_policy.delay_for_attempt(attempt));
+                self.rate_limiter.cooldown(cooldown);
+            }
+
+            match resp { 
+                Ok(resp) => return Ok(resp),
+                Err(e) if e.is_transient() && attempt < max_attempts => { 
+                    // 429s wait out the cooldown in the limiter instead
+                    if !matches!(e, TransportError::RateLimited(_)) { 
+                        tokio::time::sleep(retry_policy.delay_for_attempt(attempt)).await;
+                    }
+                    attempt += 1;
+                }
+                // the budget ran out on transient failures, anything else is returned as is
+                Err(e) if e.is_transient() && attempt > 1 => return Err(LightNodeError::RetriesExhausted { attempts: attempt, source: e }),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// sends a `method` request and returns its result, handling the envelope, ids and errors
+    pub async fn rpc<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R, LightNodeError> { 
+        self.rpc_to(&self.transport, method, params).await
+    }
+
+    pub(crate) async fn rpc_to<U: RpcTransport, P: Serialize, R: DeserializeOwned>(&self, transport: &U, method: &str, params: P) -> Result<R, LightNodeError> { 
+        let id = self.next_request_id();
+        let request = rpc_request(id, method, params).to_string();
+        let resp = self.send_request_to(transport, request).await?;
+        parse_rpc_response::<RpcResponse<R>>(&resp, id).map(|resp| resp.result)
+    }
+
+    /// fetches the block using the client's default encoding
+    pub async fn get_block(&self, slot: u64) -> Result<UiConfirmedBlock, LightNodeError> { 
+        self.get_block_with_encoding(slot, self.encoding).await
+    }
+
+    pub async fn get_block_with_encoding(&self, slot: u64, encoding: UiTransactionEncoding) -> Result<UiConfirmedBlock, LightNodeError> { 
+        self.get_block_with_options(slot, &GetBlockOptions::new(encoding)).await
+    }
+
+    /// fetches the block with only the given transaction details (eg, signatures or none) and no rewards
+    pub async fn get_block_with_details(&self, slot: u64, details: TransactionDetails) -> Result<UiConfirmedBlock, LightNodeError> { 
+        let options = GetBlockOptions::new(self.encoding).transaction_details(details).rewards(false);
+        self.get_block_with_options(slot, &options).await
+    }
+
+    pub async fn get_block_with_options(&self, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        if let Some(source) = &self.block_source { 
+            return source.get_block(slot, options).await;
+        }
+        if self.quorum > 1 { 
+            return self.get_block_with_quorum(slot, options).await;
+        }
+        self.get_block_with_options_from(&self.transport, slot, options).await
+    }
+
+    pub(crate) async fn get_block_with_options_from<U: RpcTransport>(&self, transport: &U, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        self.rpc_to(transport, "getBlock", get_block_params(slot, options, self.commitment)).await
+            .map_err(|e| map_block_error(slot, e))
+    }
+
+    /// the blockhash, previous blockhash and parent slot of the block without any of its txs
+    pub async fn get_block_summary(&self, slot: u64) -> Result<BlockSummary, LightNodeError> { 
+        let options = GetBlockOptions::new(self.encoding).transaction_details(TransactionDetails::None).rewards(false);
+        self.rpc("getBlock", get_block_params(slot, &options, self.commitment)).await
+            .map_err(|e| map_block_error(slot, e))
+    }
+
+    pub async fn get_slot(&self) -> Result<u64, LightNodeError> { 
+        self.rpc("getSlot", [serde_json::json!({ "commitment": self.commitment })]).await
+    }
+
+    /// the rpc's (estimated) unix timestamp of the block, None when the node doesnt have one
+    pub async fn get_block_time(&self, slot: u64) -> Result<Option<i64>, LightNodeError> { 
+        if let Some(source) = &self.block_source { 
+            return Ok(source.get_block(slot, &GetBlockOptions::new(self.encoding)).await?.block_time);
+        }
+        self.rpc("getBlockTime", [slot]).await
+            .map_err(|e| map_block_error(slot, e))
+    }
+
+    pub async fn get_blockhash(&self, slot: u64) -> Result<Hash, LightNodeError> { 
+        let block = self.get_block_with_details(slot, TransactionDetails::None).await?;
+        Hash::from_str(&block.blockhash)
+            .map_err(|_| LightNodeError::InvalidResponse(format!("invalid blockhash {}", block.blockhash)))
+    }
+
+    /// fetches the blocks with batched JSON-RPC requests (chunked by `max_batch_size`)
+    /// the results are in the same order as `slots` and each slot keeps its own error (eg, skipped)
+    pub async fn get_blocks_batched(&self, slots: &[u64]) -> Result<Vec<Result<UiConfirmedBlock, LightNodeError>>, LightNodeError> { 
+        let options = GetBlockOptions::new(self.encoding);
+        let mut blocks = Vec::with_capacity(slots.len());
+
+        for chunk in slots.chunks(self.max_batch_size.max(1)) { 
+            // every request gets its own id from the client so responses cant be confused across batches
+            let ids = chunk.iter().map(|_| self.next_request_id()).collect::<Vec<_>>();
+            let request = chunk.iter()
+                .zip(ids.iter())
+                .map(|(slot, id)| rpc_request(*id, "getBlock", get_block_params(*slot, &options, self.commitment)))
+                .collect::<Vec<_>>();
+            let request = serde_json::Value::Array(request).to_string();
+            let resp = self.send_request(request).await?;
+
+            // a batch request which fails as a whole returns a single error object
+            let resp: serde_json::Value = serde_json::from_str(&resp)?;
+            let responses = match resp { 
+                serde_json::Value::Array(responses) => responses,
+                resp => return Err(rpc_error(&resp).unwrap_or_else(|| { 
+                    LightNodeError::InvalidResponse("expected a batch response".to_string())
+                })),
+            };
+
+            // responses can arrive in any order so they're matched back using the id
+            let index_of_id = ids.iter()
+                .enumerate()
+                .map(|(i, id)| (*id, i))
+                .collect::<HashMap<_, _>>();
+            let mut chunk_blocks = chunk.iter()
+                .map(|slot| Err(LightNodeError::InvalidResponse(format!("no response for slot {}", slot))))
+                .collect::<Vec<_>>();
+            for resp in responses.into_iter() { 
+                let id = resp.get("id").and_then(|id| id.as_u64());
+                let (i, id) = match id.and_then(|id| index_of_id.get(&id).map(|i| (*i, id))) { 
+                    Some(x) => x,
+                    None => return Err(LightNodeError::InvalidResponse(format!("unexpected response id {:?}", resp.get("id")))),
+                };
+                chunk_blocks[i] = parse_rpc_value::<RpcResponse<UiConfirmedBlock>>(resp, id)
+                    .map(|resp| resp.result)
+                    .map_err(|e| map_block_error(chunk[i], e));
+            }
+            blocks.extend(chunk_blocks);
+        }
+
+        Ok(blocks)
+    }
+
+    /// the slots in `start_slot..=end_slot` which have a block (from `getBlocks`)
+    pub async fn get_confirmed_blocks(&self, start_slot: u64, end_slot: u64) -> Result<Vec<u64>, LightNodeError> { 
+        self.rpc("getBlocks", serde_json::json!([start_slot, end_slot, { "commitment": self.commitment }])).await
+    }
+
+    /// `fetch_block_range` with the client's default block options
+    pub async fn get_blocks(&self, start_slot: u64, end_slot: u64) -> Result<BlockRange, LightNodeError> { 
+        self.fetch_block_range(start_slot, end_slot, &GetBlockOptions::new(self.encoding)).await
+    }
+
+    /// fetches every block in `start_slot..=end_slot`, only the slots `getBlocks` lists are fetched
+    /// and the rest are reported as skipped
+    pub async fn fetch_block_range(&self, start_slot: u64, end_slot: u64, options: &GetBlockOptions) -> Result<BlockRange, LightNodeError> { 
+        let slots = self.get_confirmed_blocks(start_slot, end_slot).await?;
+
+        let mut range = BlockRange::default();
+        let mut expected_slot = start_slot;
+        for slot in slots.iter() { 
+            range.skipped.extend(expected_slot..*slot);
+            expected_slot = slot + 1;
+        }
+        range.skipped.extend(expected_slot..=end_slot);
+
+        let mut blocks = stream::iter(slots)
+            .map(|slot| async move { (slot, self.get_block_with_options(slot, options).await) })
+            .buffered(MAX_CONCURRENT_BLOCK_FETCHES);
+        while let Some((slot, block)) = blocks.next().await { 
+            match block { 
+                Ok(block) => range.blocks.push((slot, block)),
+                Err(e) => range.failed.push((slot, e)),
+            }
+        }
+
+        Ok(range)
+    }
+
+    /// polls `get_block` until the block is available (eg, for slots which havent been produced yet),
+    /// giving up with `Timeout` after `block_wait_timeout`
+    pub async fn wait_for_block(&self, slot: u64) -> Result<UiConfirmedBlock, LightNodeError> { 
+        self.wait_for_block_with_options(slot, &GetBlockOptions::new(self.encoding)).await
+    }
+
+    pub async fn wait_for_block_with_options(&self, slot: u64, options: &GetBlockOptions) -> Result<UiConfirmedBlock, LightNodeError> { 
+        let deadline = Instant::now() + self.block_wait_timeout;
+        loop { 
+            match self.get_block_with_options(slot, options).await { 
+                Err(LightNodeError::BlockUnavailable(_)) if Instant::now() < deadline => { 
+                    tracing::debug!("block {} isnt available yet, retrying", slot);
+                    // dont block the runtime so other tasks keep going
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Err(LightNodeError::BlockUnavailable(_)) => return Err(LightNodeError::Timeout(self.block_wait_timeout)),
+                r => return r,
+            }
+        }
+    }
+
+    pub async fn get_block_headers(&self, slot: u64, signature: Signature) -> Result<Vec<u8>, LightNodeError> { 
+        if let Some(source) = &self.block_source { 
+            return source.get_block_headers(slot, signature).await;
+        }
+        if self.quorum > 1 { 
+            return self.get_block_headers_with_quorum(slot, signature).await;
+        }
+        self.get_block_headers_from(&self.transport, slot, signature).await
+    }
+
+    pub(crate) async fn get_block_headers_from<U: RpcTransport>(&self, transport: &U, slot: u64, signature: Signature) -> Result<Vec<u8>, LightNodeError> { 
+        let config = serde_json::json!({ "commitment": self.commitment });
+        let headers = match self.rpc_to(transport, "getBlockHeaders", (slot, signature.as_ref(), config)).await { 
+            // older servers dont take a config so its retried without one
+            Err(LightNodeError::Rpc { code, .. }) if code == INVALID_PARAMS => { 
+                self.rpc_to(transport, "getBlockHeaders", (slot, signature.as_ref())).await
+            }
+            headers => headers,
+        };
+        headers.map_err(|e| map_block_error(slot, e))
+    }
+
+    /// polls until the tx is confirmed, for at most `block_wait_timeout`
+    pub async fn get_tx(&self, signature: Signature) -> Result<EncodedConfirmedTransactionWithStatusMeta, LightNodeError> { 
+        let params = serde_json::json!([
+            signature.to_string(),
+            { 
+                "commitment": "confirmed",
+                "encoding": "json",
+            }
+        ]);
+        let deadline = Instant::now() + self.block_wait_timeout;
+        loop { 
+            // the result is null until the tx is available
+            let tx = self.rpc::<_, Option<EncodedConfirmedTransactionWithStatusMeta>>("getTransaction", &params).await?;
+            if let Some(tx) = tx { 
+                return Ok(tx);
+            }
+            if Instant::now() >= deadline { 
+                return Err(LightNodeError::Timeout(self.block_wait_timeout));
+            }
+            tracing::debug!("tx {} isnt confirmed yet, retrying", signature);
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    pub async fn get_epoch_info(&self) -> Result<EpochInfo, LightNodeError> { 
+        self.rpc("getEpochInfo", [serde_json::json!({ "commitment": self.commitment })]).await
+    }
+
+    pub async fn get_epoch_schedule(&self) -> Result<EpochSchedule, LightNodeError> { 
+        self.rpc("getEpochSchedule", serde_json::json!([])).await
+    }
+
+    pub async fn get_vote_accounts(&self) -> Result<RpcVoteAccountStatus, LightNodeError> { 
+        self.rpc("getVoteAccounts", [serde_json::json!({ "commitment": self.commitment })]).await
+    }
+
+    /// identity -> its slot indexes in the epoch containing `slot`, None when the rpc doesnt have the epoch yet
+    pub async fn get_leader_schedule(&self, slot: u64) -> Result<Option<HashMap<String, Vec<usize>>>, LightNodeError> { 
+        self.rpc("getLeaderSchedule", serde_json::json!([slot, { "commitment": self.commitment }])).await
+    }
+
+    /// the account (None when it doesnt exist) and the slot it was read at
+    pub async fn get_account(&self, pubkey: &Pubkey) -> Result<(u64, Option<Account>), LightNodeError> { 
+        let params = serde_json::json!([pubkey.to_string(), { "encoding": "base64", "commitment": self.commitment }]);
+        let resp = self.rpc::<_, ContextValue<Option<UiAccount>>>("getAccountInfo", params).await?;
+        let account = resp.value.as_ref().map(|account| decode_account(pubkey, account)).transpose()?;
+        Ok((resp.context.slot, account))
+    }
+
+    /// the accounts in the same order as `pubkeys` (None where one doesnt exist) and the slot they were read at,
+    /// at most `MAX_MULTIPLE_ACCOUNTS` per call
+    pub async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<(u64, Vec<Option<Account>>), LightNodeError> { 
+        let params = serde_json::json!([
+            pubkeys.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            { "encoding": "base64", "commitment": self.commitment }
+        ]);
+        let resp = self.rpc::<_, ContextValue<Vec<Option<UiAccount>>>>("getMultipleAccounts", params).await?;
+        if resp.value.len() != pubkeys.len() { 
+            return Err(LightNodeError::InvalidResponse(format!("expected {} accounts got {}", pubkeys.len(), resp.value.len())));
+        }
+        let accounts = pubkeys.iter().zip(resp.value.iter())
+            .map(|(pubkey, account)| account.as_ref().map(|account| decode_account(pubkey, account)).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((resp.context.slot, accounts))
+    }
+}
+
+// one-off helpers, these build a new client per call so prefer reusing a `LightClient`
+pub async fn get_block(slot: u64, endpoint: &str) -> Result<UiConfirmedBlock, LightNodeError> { 
+    LightClient::new(endpoint).get_block(slot).await
+}
+
+pub async fn get_block_headers(slot: u64, signature: Signature, endpoint: &str) -> Result<Vec<u8>, LightNodeError> { 
+    LightClient::new(endpoint).get_block_headers(slot, signature).await
+}
+
+pub async fn get_tx(signature: Signature, endpoint: &str) -> Result<EncodedConfirmedTransactionWithStatusMeta, LightNodeError> { 
+    LightClient::new(endpoint).get_tx(signature).await
+}
@@ -0,0 +1,99 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Serialize;
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::stake::total_stake;
+use crate::transport::RpcTransport;
+use crate::verify::{VOTE_SLOTS_AHEAD, stake_pct};
+use crate::vote::confirmed_stake_for_slot;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlotConfirmation { 
+    pub slot: u64,
+    // stake of the vote accounts which voted on the slot in the blocks after it
+    pub confirmed_stake: u128,
+    pub total_stake: u128,
+    pub confirmed_stake_pct: f64,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse { 
+    error: String,
+}
+
+fn respond<R: Serialize>(result: Result<R, LightNodeError>) -> Response { 
+    match result { 
+        Ok(x) => Json(x).into_response(),
+        Err(e) => { 
+            let status = match e { 
+                LightNodeError::SlotSkipped(_) => StatusCode::NOT_FOUND,
+                LightNodeError::HistoricalStakesUnavailable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+                // everything else is the rpc failing us
+                _ => StatusCode::BAD_GATEWAY,
+            };
+            (status, Json(ErrorResponse { error: e.to_string() })).into_response()
+        }
+    }
+}
+
+async fn health() -> &'static str { 
+    "ok"
+}
+
+async fn metrics<T: RpcTransport + 'static>(State(client): State<Arc<LightClient<T>>>) -> impl IntoResponse { 
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], client.metrics.encode())
+}
+
+async fn verify<T: RpcTransport + 'static>(State(client): State<Arc<LightClient<T>>>, Path(slot): Path<u64>) -> Response { 
+    respond(client.verify_block(slot).await.map(|result| result.summary()))
+}
+
+async fn confirmation<T: RpcTransport + 'static>(State(client): State<Arc<LightClient<T>>>, Path(slot): Path<u64>) -> Response { 
+    respond(client.slot_confirmation(slot).await)
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// how much of the stake voted on `slot` in the blocks after it
+    pub async fn slot_confirmation(&self, slot: u64) -> Result<SlotConfirmation, LightNodeError> { 
+        let votes = self.parse_block_votes(slot, VOTE_SLOTS_AHEAD).await?;
+        let stakes = self.stakes_for_slot(slot).await?;
+        let confirmed_stake = confirmed_stake_for_slot(&votes, &stakes.stakes, slot);
+        let total_stake = total_stake(&stakes.stakes);
+        Ok(SlotConfirmation { 
+            slot,
+            confirmed_stake,
+            total_stake,
+            confirmed_stake_pct: stake_pct(confirmed_stake, total_stake),
+        })
+    }
+}
+
+/// `GET /health`, `GET /metrics` (prometheus), `GET /slot/:slot/verify` (a `VerificationSummary`) and `GET /slot/:slot/confirmation`
+/// (a `SlotConfirmation`), errors are returned as `{"error": ..}`
+pub fn router<T: RpcTransport + 'static>(client: Arc<LightClient<T>>) -> Router { 
+    Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(metrics::<T>))
+        .route("/slot/:slot/verify", get(verify::<T>))
+        .route("/slot/:slot/confirmation", get(confirmation::<T>))
+        .with_state(client)
+}
+
+/// serves `router` on `addr` until the server fails
+pub async fn serve<T: RpcTransport + 'static>(client: Arc<LightClient<T>>, addr: SocketAddr) -> Result<(), LightNodeError> { 
+    tracing::info!("serving the http api on {}", addr);
+    axum::Server::try_bind(&addr)
+        .map_err(|e| LightNodeError::Server(e.to_string()))?
+        .serve(router(client).into_make_service())
+        .await
+        .map_err(|e| LightNodeError::Server(e.to_string()))
+}
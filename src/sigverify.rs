@@ -0,0 +1,140 @@
+use ed25519_dalek::{PublicKey, Signature as DalekSignature};
+use solana_sdk::transaction::VersionedTransaction;
+
+/// verifies the signatures of the first `num_required_signatures` account keys (the signers)
+/// returns the indices of any signatures which are missing or fail to verify
+pub fn verify_transaction_signatures(tx: &VersionedTransaction) -> Result<(), Vec<usize>> { 
+    let msg = &tx.message;
+    let num_required = msg.header().num_required_signatures as usize;
+    let account_keys = msg.static_account_keys();
+    let msg_bytes = msg.serialize();
+
+    let failed = (0..num_required.max(tx.signatures.len()))
+        .filter(|&i| { 
+            match (tx.signatures.get(i), account_keys.get(i)) { 
+                (Some(signature), Some(pubkey)) if i < num_required => !signature.verify(pubkey.as_ref(), &msg_bytes[..]),
+                // missing signature / signer or an extra signature
+                _ => true,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() { 
+        Ok(())
+    } else { 
+        Err(failed)
+    }
+}
+
+/// `verify_transaction_signatures` of every tx, with all the signatures checked in a single ed25519
+/// batch. when the batch fails the txs are checked one by one to find the ones which failed
+pub fn verify_transactions_batch(txs: &[VersionedTransaction]) -> Vec<Result<(), Vec<usize>>> { 
+    let msg_bytes = txs.iter().map(|tx| tx.message.serialize()).collect::<Vec<_>>();
+
+    // txs which cant go into the batch (eg, a missing signature) are checked on their own
+    let mut results = vec![None; txs.len()];
+    let mut messages: Vec<&[u8]> = vec![];
+    let mut signatures = vec![];
+    let mut pubkeys = vec![];
+    for (i, (tx, msg)) in txs.iter().zip(msg_bytes.iter()).enumerate() { 
+        let num_required = tx.message.header().num_required_signatures as usize;
+        let account_keys = tx.message.static_account_keys();
+        let batch_keys = tx.signatures.iter()
+            .zip(account_keys.iter())
+            .map(|(signature, pubkey)| Some((DalekSignature::from_bytes(signature.as_ref()).ok()?, PublicKey::from_bytes(pubkey.as_ref()).ok()?)))
+            .collect::<Option<Vec<_>>>();
+        match batch_keys { 
+            Some(batch_keys) if tx.signatures.len() == num_required && account_keys.len() >= num_required => { 
+                for (signature, pubkey) in batch_keys { 
+                    messages.push(&msg[..]);
+                    signatures.push(signature);
+                    pubkeys.push(pubkey);
+                }
+            }
+            _ => results[i] = Some(verify_transaction_signatures(tx)),
+        }
+    }
+
+    let batch_verified = messages.is_empty() || ed25519_dalek::verify_batch(&messages, &signatures, &pubkeys).is_ok();
+    txs.iter()
+        .zip(results)
+        .map(|(tx, result)| match result { 
+            Some(result) => result,
+            None if batch_verified => Ok(()),
+            None => verify_transaction_signatures(tx),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests { 
+    use solana_sdk::{
+        hash::Hash,
+        signature::{Keypair, Signature, Signer},
+        transaction::Transaction,
+        vote::{instruction::vote, state::Vote},
+    };
+
+    use super::*;
+
+    // a vote paid for by the node identity and signed by a separate authorized voter (two signers)
+    fn vote_tx() -> VersionedTransaction { 
+        let node = Keypair::new();
+        let authorized_voter = Keypair::new();
+        let vote_account = Keypair::new().pubkey();
+        let ix = vote(&vote_account, &authorized_voter.pubkey(), Vote::new(vec![42], Hash::new_unique()));
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&node.pubkey()), &[&node, &authorized_voter], Hash::new_unique());
+        VersionedTransaction::from(tx)
+    }
+
+    #[test]
+    fn test_valid_vote() { 
+        let tx = vote_tx();
+        assert_eq!(tx.message.header().num_required_signatures, 2);
+        assert_eq!(verify_transaction_signatures(&tx), Ok(()));
+        assert_eq!(verify_transactions_batch(&[tx.clone(), vote_tx()]), vec![Ok(()), Ok(())]);
+    }
+
+    #[test]
+    fn test_corrupted_signature() { 
+        let mut tx = vote_tx();
+        let mut signature = tx.signatures[1].as_ref().to_vec();
+        signature[0] ^= 1;
+        tx.signatures[1] = Signature::try_from(&signature[..]).unwrap();
+        assert_eq!(verify_transaction_signatures(&tx), Err(vec![1]));
+        // the batch fails and the valid tx next to it is still found
+        assert_eq!(verify_transactions_batch(&[vote_tx(), tx]), vec![Ok(()), Err(vec![1])]);
+    }
+
+    #[test]
+    fn test_missing_required_signer() { 
+        let mut tx = vote_tx();
+        tx.signatures.pop();
+        assert_eq!(verify_transaction_signatures(&tx), Err(vec![1]));
+        assert_eq!(verify_transactions_batch(&[tx, vote_tx()]), vec![Err(vec![1]), Ok(())]);
+    }
+
+    #[test]
+    fn test_extra_signature() { 
+        // the extra one is a valid signature over the message, it still isnt one the header asks for
+        let mut tx = vote_tx();
+        let extra = Keypair::new().sign_message(&tx.message.serialize());
+        tx.signatures.push(extra);
+        assert_eq!(verify_transaction_signatures(&tx), Err(vec![2]));
+        assert_eq!(verify_transactions_batch(&[tx, vote_tx()]), vec![Err(vec![2]), Ok(())]);
+    }
+
+    #[test]
+    fn test_batch_with_one_bad_signature() { 
+        let mut txs = (0..10).map(|_| vote_tx()).collect::<Vec<_>>();
+        // a valid signature, of another tx
+        txs[6].signatures[0] = vote_tx().signatures[0];
+
+        let results = verify_transactions_batch(&txs);
+        for (i, result) in results.iter().enumerate() { 
+            assert_eq!(*result, if i == 6 { Err(vec![0]) } else { Ok(()) }, "tx {}", i);
+        }
+        // the same as checking each tx on its own
+        assert_eq!(results, txs.iter().map(verify_transaction_signatures).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use solana_sdk::{hash::Hash, pubkey::Pubkey, slot_hashes::SlotHashes, sysvar};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::transport::RpcTransport;
+use crate::vote::{ParsedVote, VoteSource};
+
+// slots kept behind the newest known slot hash (the sysvar itself only has the last 512)
+pub const SLOT_HASH_WINDOW: u64 = 2048;
+
+/// how a vote's (slot, bank hash) lines up with the known slot hashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotHashCheck { 
+    Consistent,
+    // the slot is known with another bank hash
+    WrongHash { expected: Hash },
+    // the slot is in the range `SlotHashes` covers but isnt in it (skipped or on another fork)
+    MissingSlot,
+    // nothing to check the vote against (eg, the slot is newer than the sysvar)
+    Unknown,
+}
+
+impl SlotHashCheck { 
+    pub fn is_suspicious(&self) -> bool { 
+        matches!(self, SlotHashCheck::WrongHash { .. } | SlotHashCheck::MissingSlot)
+    }
+}
+
+/// a block vote whose slot / bank hash doesnt match the known slot hashes, its stake isnt counted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuspiciousVote { 
+    pub vote_account: Pubkey,
+    pub node_pubkey: Option<Pubkey>,
+    pub slot: u64,
+    pub bank_hash: Hash,
+    pub check: SlotHashCheck,
+}
+
+/// the bank hashes of recent slots, from the `SlotHashes` sysvar and the slots verified by `verify_slot`
+#[derive(Debug, Default)]
+pub struct SlotHashHistory { 
+    // every slot of the sysvar's fork from the first key to the last one (skipped slots arent in it)
+    sysvar_hashes: BTreeMap<u64, Hash>,
+    // bank hashes recomputed from the block headers and voted on by a supermajority
+    verified: BTreeMap<u64, Hash>,
+}
+
+impl SlotHashHistory { 
+    pub fn new() -> Self { 
+        SlotHashHistory::default()
+    }
+
+    pub fn newest(&self) -> Option<u64> { 
+        self.sysvar_hashes.keys().next_back().copied()
+    }
+
+    /// merges a fetch of the sysvar, a fetch which doesnt overlap the known range replaces it
+    /// (the slots in between would look missing otherwise)
+    pub fn insert_sysvar(&mut self, slot_hashes: &[(u64, Hash)]) { 
+        let oldest = match slot_hashes.iter().map(|(slot, _)| *slot).min() { 
+            Some(oldest) => oldest,
+            None => return,
+        };
+        if self.newest().map_or(true, |newest| newest + 1 < oldest) { 
+            self.sysvar_hashes.clear();
+        }
+        // the newer fetch wins where they overlap (eg, the fork switched)
+        self.sysvar_hashes.split_off(&oldest);
+        self.sysvar_hashes.extend(slot_hashes.iter().copied());
+        self.prune();
+    }
+
+    pub fn insert_verified(&mut self, slot: u64, bank_hash: Hash) { 
+        self.verified.insert(slot, bank_hash);
+        self.prune();
+    }
+
+    fn prune(&mut self) { 
+        let newest = self.newest().into_iter().chain(self.verified.keys().next_back().copied()).max().unwrap_or(0);
+        let oldest = newest.saturating_sub(SLOT_HASH_WINDOW);
+        self.sysvar_hashes = self.sysvar_hashes.split_off(&oldest);
+        self.verified = self.verified.split_off(&oldest);
+    }
+
+    pub fn check(&self, slot: u64, bank_hash: &Hash) -> SlotHashCheck { 
+        let known = self.verified.get(&slot).or_else(|| self.sysvar_hashes.get(&slot));
+        match known { 
+            Some(expected) if expected == bank_hash => SlotHashCheck::Consistent,
+            Some(expected) => SlotHashCheck::WrongHash { expected: *expected },
+            None => match (self.sysvar_hashes.keys().next(), self.sysvar_hashes.keys().next_back()) { 
+                (Some(oldest), Some(newest)) if *oldest <= slot && slot <= *newest => SlotHashCheck::MissingSlot,
+                _ => SlotHashCheck::Unknown,
+            },
+        }
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// fetches the `SlotHashes` sysvar into the known slot hashes
+    pub async fn refresh_slot_hashes(&self) -> Result<(), LightNodeError> { 
+        let (fetched_slot, account) = self.get_account(&sysvar::slot_hashes::id()).await?;
+        let account = account
+            .ok_or_else(|| LightNodeError::InvalidResponse("no slot hashes sysvar".to_string()))?;
+        let slot_hashes: SlotHashes = bincode::deserialize(&account.data)?;
+        self.slot_hashes.write().unwrap().insert_sysvar(&slot_hashes);
+        tracing::debug!("fetched {} slot hashes at slot {}", slot_hashes.len(), fetched_slot);
+
+        Ok(())
+    }
+
+    /// flags the block votes which contradict the known slot hashes (refetching the sysvar when the votes are
+    /// newer than it), theyre kept for `take_suspicious_votes` and no longer count
+    pub(crate) async fn check_slot_hashes(&self, votes: &mut [ParsedVote]) -> Result<(), LightNodeError> { 
+        let newest_vote = votes.iter().filter(|vote| vote.source == VoteSource::Block).map(|vote| vote.slot).max();
+        let newest_vote = match newest_vote { Some(slot) => slot, None => return Ok(()) };
+        if self.slot_hashes.read().unwrap().newest().map_or(true, |newest| newest < newest_vote) { 
+            self.refresh_slot_hashes().await?;
+        }
+
+        let slot_hashes = self.slot_hashes.read().unwrap();
+        let mut suspicious = vec![];
+        for vote in votes.iter_mut().filter(|vote| vote.source == VoteSource::Block) { 
+            let check = slot_hashes.check(vote.slot, &vote.bank_hash);
+            if !check.is_suspicious() { 
+                continue;
+            }
+            tracing::warn!(vote_account = %vote.vote_account, slot = vote.slot, bank_hash = %vote.bank_hash, "vote doesnt match the slot hashes: {:?}", check);
+            vote.slot_hash_mismatch = true;
+            suspicious.push(SuspiciousVote { 
+                vote_account: vote.vote_account,
+                node_pubkey: vote.node_pubkey,
+                slot: vote.slot,
+                bank_hash: vote.bank_hash,
+                check,
+            });
+        }
+        self.metrics.suspicious_votes.inc_by(suspicious.len() as u64);
+        self.suspicious_votes.lock().unwrap().extend(suspicious);
+
+        Ok(())
+    }
+
+    /// the block votes found to contradict the slot hashes so far (each one is only returned once)
+    pub fn take_suspicious_votes(&self) -> Vec<SuspiciousVote> { 
+        std::mem::take(&mut *self.suspicious_votes.lock().unwrap())
+    }
+}
@@ -0,0 +1,363 @@
+use std::{collections::{HashMap, HashSet}, fmt, str::FromStr};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_response::RpcVoteAccountStatus;
+use solana_sdk::{
+    pubkey::Pubkey,
+    stake::state::Delegation,
+    stake_history::StakeHistory,
+    sysvar,
+};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::transport::RpcTransport;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StakeInfo { 
+    pub node_pubkey: Pubkey,
+    pub activated_stake: u64,
+}
+
+/// vote account -> node identity + activated stake
+pub fn stakes_from_vote_accounts(vote_accounts: &RpcVoteAccountStatus) -> Result<HashMap<Pubkey, StakeInfo>, LightNodeError> { 
+    // collecting into a map keeps a single entry for an account listed as both current and delinquent
+    vote_accounts.current
+        .iter()
+        .chain(vote_accounts.delinquent.iter())
+        .map(|x| { 
+            let vote_pubkey = Pubkey::from_str(&x.vote_pubkey)?;
+            let node_pubkey = Pubkey::from_str(&x.node_pubkey)?;
+            Ok((vote_pubkey, StakeInfo { node_pubkey, activated_stake: x.activated_stake }))
+        })
+        .collect()
+}
+
+/// how the stakes of an `EpochStakes` were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakeSource { 
+    // `getVoteAccounts`, exact but only has the current epoch
+    VoteAccounts,
+    // estimated from the epoch's leader schedule (see `stakes_from_leader_schedule`)
+    LeaderSchedule,
+}
+
+/// a stake map and the epoch its stakes are from
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochStakes { 
+    pub epoch: u64,
+    // slot the (current) stakes were fetched at
+    pub slot: u64,
+    pub source: StakeSource,
+    // vote account -> node identity + activated stake
+    pub stakes: HashMap<Pubkey, StakeInfo>,
+}
+
+/// node identity -> stake summed over its vote accounts (what the leader schedule is weighted by)
+pub fn node_stakes(stakes: &HashMap<Pubkey, StakeInfo>) -> HashMap<Pubkey, u64> { 
+    let mut node_stakes = HashMap::new();
+    for stake_info in stakes.values() { 
+        *node_stakes.entry(stake_info.node_pubkey).or_insert(0) += stake_info.activated_stake;
+    }
+    node_stakes
+}
+
+/// estimates the stakes of an epoch from its leader schedule: the leader slots are sampled by stake so
+/// a node's share of the slots is roughly its share of the stake. each node gets that share of the current
+/// total stake, split evenly over its current vote accounts (nodes without one cant be matched to votes)
+pub fn stakes_from_leader_schedule(schedule: &[Pubkey], current: &HashMap<Pubkey, StakeInfo>) -> HashMap<Pubkey, StakeInfo> { 
+    let mut leader_slots: HashMap<Pubkey, u64> = HashMap::new();
+    for leader in schedule.iter() { 
+        *leader_slots.entry(*leader).or_default() += 1;
+    }
+    let mut vote_accounts: HashMap<Pubkey, Vec<Pubkey>> = HashMap::new();
+    for (vote_account, stake_info) in current.iter() { 
+        vote_accounts.entry(stake_info.node_pubkey).or_default().push(*vote_account);
+    }
+
+    let total_stake = total_stake(current);
+    let num_slots = schedule.len().max(1) as u128;
+    let mut stakes = HashMap::new();
+    for (node_pubkey, slots) in leader_slots { 
+        let accounts = match vote_accounts.get(&node_pubkey) { Some(accounts) => accounts, None => continue };
+        let node_stake = total_stake * slots as u128 / num_slots;
+        let activated_stake = u64::try_from(node_stake / accounts.len() as u128).unwrap_or(u64::MAX);
+        for vote_account in accounts.iter() { 
+            stakes.insert(*vote_account, StakeInfo { node_pubkey, activated_stake });
+        }
+    }
+    stakes
+}
+
+/// summed as u128 so it (and the threshold math on it) cant overflow
+pub fn total_stake(stakes: &HashMap<Pubkey, StakeInfo>) -> u128 { 
+    stakes.values().map(|x| x.activated_stake as u128).sum()
+}
+
+/// fraction of the total stake a bank hash needs to be confirmed, `num / denom` so the
+/// comparison is exact integer math
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Threshold { 
+    pub num: u64,
+    pub denom: u64,
+}
+
+impl Threshold { 
+    pub const SUPERMAJORITY: Threshold = Threshold { num: 2, denom: 3 };
+
+    /// `num / denom` has to be in (0, 1]
+    pub fn new(num: u64, denom: u64) -> Result<Self, LightNodeError> { 
+        if num == 0 || num > denom { 
+            return Err(LightNodeError::InvalidThreshold(format!("{}/{} isnt between 0 and 1", num, denom)));
+        }
+        Ok(Threshold { num, denom })
+    }
+
+    /// stake * denom >= total_stake * num
+    pub fn is_met(&self, stake: u128, total_stake: u128) -> bool { 
+        match (stake.checked_mul(self.denom as u128), total_stake.checked_mul(self.num as u128)) { 
+            (Some(stake), Some(total_stake)) => stake >= total_stake,
+            // summed stakes times a large denom can go past u128
+            _ => mul_wide(stake, self.denom) >= mul_wide(total_stake, self.num),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 { 
+        self.num as f64 / self.denom as f64
+    }
+}
+
+// a * b as (high, low) 128 bit halves, compared as a tuple its the 256 bit product
+fn mul_wide(a: u128, b: u64) -> (u128, u128) { 
+    let b = b as u128;
+    let low = (a as u64 as u128) * b;
+    let high = (a >> 64) * b;
+    // the low half of `high` lands in the upper 64 bits of the result's low half
+    let middle = (low >> 64) + (high as u64 as u128);
+    ((high >> 64) + (middle >> 64), (middle << 64) | (low as u64 as u128))
+}
+
+impl Default for Threshold { 
+    fn default() -> Self { 
+        Threshold::SUPERMAJORITY
+    }
+}
+
+impl fmt::Display for Threshold { 
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { 
+        write!(f, "{}/{}", self.num, self.denom)
+    }
+}
+
+/// parses a fraction (`2/3`) or a decimal (`0.8`, kept exact as `8/10`)
+impl FromStr for Threshold { 
+    type Err = LightNodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { 
+        let invalid = || LightNodeError::InvalidThreshold(format!("{} isnt a fraction or a decimal", s));
+        let s = s.trim();
+        if let Some((num, denom)) = s.split_once('/') { 
+            let num = num.trim().parse().map_err(|_| invalid())?;
+            let denom = denom.trim().parse().map_err(|_| invalid())?;
+            return Threshold::new(num, denom);
+        }
+
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        // more digits than this would overflow the denominator
+        if frac.len() > 18 || !frac.chars().all(|c| c.is_ascii_digit()) { 
+            return Err(invalid());
+        }
+        let denom = 10u64.pow(frac.len() as u32);
+        let int = if int.is_empty() { 0 } else { int.parse::<u64>().map_err(|_| invalid())? };
+        let frac = if frac.is_empty() { 0 } else { frac.parse::<u64>().map_err(|_| invalid())? };
+        let num = int.checked_mul(denom).and_then(|x| x.checked_add(frac)).ok_or_else(invalid)?;
+        Threshold::new(num, denom)
+    }
+}
+
+/// the stake of the delegation which was effective at `epoch` (taking warmup and cooldown into account)
+/// `activated_stake` from `getVoteAccounts` is only right for the current epoch
+pub fn effective_stake_at_epoch(delegation: &Delegation, epoch: u64, stake_history: &StakeHistory) -> u64 { 
+    delegation.stake(epoch, Some(stake_history))
+}
+
+/// vote account -> effective stake delegated to it at `epoch`
+pub fn vote_stakes_at_epoch(delegations: &[Delegation], epoch: u64, stake_history: &StakeHistory) -> HashMap<Pubkey, u64> { 
+    let mut stakes = HashMap::new();
+    for delegation in delegations.iter() { 
+        let stake = effective_stake_at_epoch(delegation, epoch, stake_history);
+        if stake > 0 { 
+            *stakes.entry(delegation.voter_pubkey).or_insert(0) += stake;
+        }
+    }
+    stakes
+}
+
+/// vote account -> total stake delegated to it (including stake which is still activating or deactivating)
+pub fn delegation_map(delegations: &[Delegation]) -> HashMap<Pubkey, u64> { 
+    let mut stakes = HashMap::new();
+    for delegation in delegations.iter() { 
+        *stakes.entry(delegation.voter_pubkey).or_insert(0) += delegation.stake;
+    }
+    stakes
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// the `StakeHistory` sysvar (effective, activating and deactivating stake of past epochs)
+    pub async fn get_stake_history(&self) -> Result<StakeHistory, LightNodeError> { 
+        let account = self.get_account(&sysvar::stake_history::id()).await?.1
+            .ok_or_else(|| LightNodeError::InvalidResponse("no stake history sysvar".to_string()))?;
+        Ok(bincode::deserialize(&account.data)?)
+    }
+
+    /// the delegation of every stake account (a few hundred thousand on mainnet so this takes a while)
+    pub async fn get_stake_delegations(&self) -> Result<Vec<Delegation>, LightNodeError> { 
+        let mut accounts = self.stake_delegations_stream();
+        let mut delegations = vec![];
+        while let Some(account) = accounts.next().await { 
+            let (_, delegation) = account?;
+            delegations.push(delegation);
+        }
+        Ok(delegations)
+    }
+
+    /// `delegation_map` of every stake account
+    pub async fn build_delegation_map(&self) -> Result<HashMap<Pubkey, u64>, LightNodeError> { 
+        Ok(delegation_map(&self.get_stake_delegations().await?))
+    }
+
+    /// (vote account, stake from the delegations, `activated_stake` from `getVoteAccounts`) for every vote
+    /// account where the two disagree, the delegations are counted with their effective stake since
+    /// activating / deactivating stake isnt part of `activated_stake`
+    pub async fn reconcile_stakes(&self) -> Result<Vec<(Pubkey, u64, u64)>, LightNodeError> { 
+        let stakes = self.leader_stakes().await?;
+        let stake_history = self.get_stake_history().await?;
+        let delegations = self.get_stake_delegations().await?;
+        let delegated = vote_stakes_at_epoch(&delegations, stakes.epoch, &stake_history);
+
+        let vote_accounts = stakes.stakes.keys()
+            .chain(delegated.keys())
+            .collect::<HashSet<_>>();
+        let mut discrepancies = vec![];
+        for vote_account in vote_accounts { 
+            let delegated_stake = delegated.get(vote_account).copied().unwrap_or(0);
+            let activated_stake = stakes.stakes.get(vote_account).map(|x| x.activated_stake).unwrap_or(0);
+            if delegated_stake != activated_stake { 
+                tracing::warn!("stake of {} is {} from its delegations but {} from getVoteAccounts", vote_account, delegated_stake, activated_stake);
+                discrepancies.push((*vote_account, delegated_stake, activated_stake));
+            }
+        }
+        discrepancies.sort();
+        Ok(discrepancies)
+    }
+}
+
+#[cfg(test)]
+mod tests { 
+    use solana_sdk::stake_history::StakeHistoryEntry;
+
+    use super::*;
+
+    fn vote_account_info(vote_pubkey: &Pubkey, node_pubkey: &Pubkey, activated_stake: u64) -> serde_json::Value { 
+        serde_json::json!({ 
+            "votePubkey": vote_pubkey.to_string(),
+            "nodePubkey": node_pubkey.to_string(),
+            "activatedStake": activated_stake,
+            "epochVoteAccount": true,
+            "commission": 10,
+            "lastVote": 1000,
+            "epochCredits": [],
+            "rootSlot": 968,
+        })
+    }
+
+    #[test]
+    fn test_node_with_two_vote_accounts() { 
+        let (node, other_node) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (vote_a, vote_b, vote_c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let vote_accounts: RpcVoteAccountStatus = serde_json::from_value(serde_json::json!({ 
+            "current": [vote_account_info(&vote_a, &node, 300), vote_account_info(&vote_c, &other_node, 100)],
+            "delinquent": [vote_account_info(&vote_b, &node, 200)],
+        }))
+        .unwrap();
+
+        let stakes = EpochStakes { 
+            epoch: 2,
+            slot: 1000,
+            source: StakeSource::VoteAccounts,
+            stakes: stakes_from_vote_accounts(&vote_accounts).unwrap(),
+        };
+        // the votes are counted per vote account
+        assert_eq!(stakes.stakes.len(), 3);
+        assert_eq!(stakes.stakes[&vote_a], StakeInfo { node_pubkey: node, activated_stake: 300 });
+        assert_eq!(stakes.stakes[&vote_b], StakeInfo { node_pubkey: node, activated_stake: 200 });
+        assert_eq!(stakes.stakes[&vote_c], StakeInfo { node_pubkey: other_node, activated_stake: 100 });
+        assert_eq!(total_stake(&stakes.stakes), 600);
+
+        // and the leader schedule per node
+        let node_stakes = node_stakes(&stakes.stakes);
+        assert_eq!(node_stakes.len(), 2);
+        assert_eq!(node_stakes[&node], 500);
+        assert_eq!(node_stakes[&other_node], 100);
+    }
+
+    // the cluster's stake at the end of each epoch, 1/4 of the effective stake can (de)activate per epoch
+    fn stake_history() -> StakeHistory { 
+        let mut stake_history = StakeHistory::default();
+        for (epoch, effective, activating, deactivating) in [(10, 10_000, 4_000, 0), (11, 12_500, 1_500, 0), (20, 20_000, 0, 8_000), (21, 18_000, 0, 3_000)] { 
+            stake_history.add(epoch, StakeHistoryEntry { effective, activating, deactivating });
+        }
+        stake_history
+    }
+
+    #[test]
+    fn test_effective_stake_warmup() { 
+        let stake_history = stake_history();
+        let delegation = Delegation::new(&Pubkey::new_unique(), 1_000, 10);
+        assert_eq!(effective_stake_at_epoch(&delegation, 9, &stake_history), 0);
+        // all of it is still activating
+        assert_eq!(effective_stake_at_epoch(&delegation, 10, &stake_history), 0);
+        // the delegation is 1/4 of the activating stake so it gets 1/4 of the 2500 which can warm up
+        assert_eq!(effective_stake_at_epoch(&delegation, 11, &stake_history), 625);
+        assert_eq!(effective_stake_at_epoch(&delegation, 12, &stake_history), 1_000);
+        assert_eq!(effective_stake_at_epoch(&delegation, 15, &stake_history), 1_000);
+    }
+
+    #[test]
+    fn test_effective_stake_cooldown() { 
+        let stake_history = stake_history();
+        let mut delegation = Delegation::new(&Pubkey::new_unique(), 1_000, 10);
+        delegation.deactivation_epoch = 20;
+        // deactivating stake is still effective in its deactivation epoch
+        assert_eq!(effective_stake_at_epoch(&delegation, 20, &stake_history), 1_000);
+        // 1/8 of the deactivating stake, so 1/8 of the 5000 which can cool down
+        assert_eq!(effective_stake_at_epoch(&delegation, 21, &stake_history), 375);
+        assert_eq!(effective_stake_at_epoch(&delegation, 22, &stake_history), 0);
+
+        // the partially active stake is what gets counted for the vote account (next to a delegation
+        // from before the history which counts as fully active)
+        let voter_pubkey = delegation.voter_pubkey;
+        let other = Delegation::new(&voter_pubkey, 500, 0);
+        let stakes = vote_stakes_at_epoch(&[delegation, other], 21, &stake_history);
+        assert_eq!(stakes[&voter_pubkey], 875);
+    }
+
+    #[test]
+    fn test_threshold_near_max_stake() { 
+        // three validators with (almost) all the stake a u64 can hold
+        let max = u64::MAX as u128;
+        let total_stake = 3 * max;
+        assert!(Threshold::SUPERMAJORITY.is_met(2 * max, total_stake));
+        assert!(!Threshold::SUPERMAJORITY.is_met(2 * max - 1, total_stake));
+
+        // stake * denom is past u128, unchecked this wrapped around to a wrong answer
+        let threshold = Threshold::new(u64::MAX - 1, u64::MAX).unwrap();
+        assert!(total_stake.checked_mul(u64::MAX as u128).is_none());
+        assert!(threshold.is_met(total_stake, total_stake));
+        // exactly at the threshold
+        assert!(threshold.is_met(total_stake - 3, total_stake));
+        assert!(!threshold.is_met(total_stake - 4, total_stake));
+        assert!(!threshold.is_met(2 * max, total_stake));
+    }
+}
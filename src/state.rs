@@ -0,0 +1,46 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::LightNodeError;
+
+pub const STATE_FILE: &str = "state.json";
+
+/// what a watcher verified so far, so it can resume where it stopped after a restart
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchState { 
+    pub highest_verified: Option<u64>,
+    // highest slot rooted by a supermajority of the parsed votes
+    pub root: Option<u64>,
+}
+
+impl WatchState { 
+    /// the state saved in `dir`, the default when nothing was saved yet
+    pub fn load(dir: &Path) -> Result<Self, LightNodeError> { 
+        match fs::read(dir.join(STATE_FILE)) { 
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(WatchState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// writes the state to `dir` (through a temp file so a crash mid write doesnt corrupt it)
+    pub fn save(&self, dir: &Path) -> Result<(), LightNodeError> { 
+        write_file(dir, STATE_FILE, &serde_json::to_vec(self)?)
+    }
+
+    /// records a verified slot, neither slot ever goes back
+    pub fn update(&mut self, slot: u64, root: Option<u64>) { 
+        self.highest_verified = Some(self.highest_verified.map_or(slot, |x| x.max(slot)));
+        self.root = root.max(self.root);
+    }
+}
+
+// writes `file` in `dir` through a temp file, so a crash mid write leaves the previous version
+pub(crate) fn write_file(dir: &Path, file: &str, bytes: &[u8]) -> Result<(), LightNodeError> { 
+    fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{}.tmp", file));
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, dir.join(file))?;
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use solana_sdk::{pubkey::Pubkey, vote::state::VoteStateVersions};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::lookup_table::MAX_MULTIPLE_ACCOUNTS;
+use crate::transport::RpcTransport;
+use crate::vote::ParsedVote;
+
+// a validator whose on-chain tower is this many slots behind the highest tower is lagging
+pub const DEFAULT_MAX_TOWER_LAG: u64 = 150;
+
+/// a validator's tower as stored in its vote account
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tower { 
+    pub node_pubkey: Pubkey,
+    pub root: Option<u64>,
+    // (slot, confirmation count), oldest first
+    pub lockouts: Vec<(u64, u32)>,
+    // slot of the bank the account was read from
+    pub fetched_slot: u64,
+}
+
+impl Tower { 
+    pub fn from_account_data(data: &[u8], fetched_slot: u64) -> Result<Self, LightNodeError> { 
+        // every layout the account has had on chain is converted to the current one
+        let versions: VoteStateVersions = bincode::deserialize(data)
+            .map_err(|e| LightNodeError::InvalidResponse(format!("invalid vote state: {:?}", e)))?;
+        let vote_state = versions.convert_to_current();
+        Ok(Tower { 
+            node_pubkey: vote_state.node_pubkey,
+            root: vote_state.root_slot,
+            lockouts: vote_state.votes.iter().map(|vote| (vote.slot(), vote.confirmation_count())).collect(),
+            fetched_slot,
+        })
+    }
+
+    pub fn last_voted_slot(&self) -> Option<u64> { 
+        self.lockouts.last().map(|(slot, _)| *slot)
+    }
+}
+
+/// why a vote from a block doesnt line up with the voter's on-chain tower
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TowerInconsistency { 
+    // the vote's root is older than the root of a tower from before it was cast
+    RootRegressed { vote_root: u64, on_chain_root: u64 },
+    // a newer tower has fewer confirmations on the slot than the vote did
+    LockoutMismatch { slot: u64, on_chain: u32, voted: u32 },
+}
+
+/// the on-chain towers of the vote accounts, refreshed with `LightClient::refresh_towers`
+#[derive(Debug, Default)]
+pub struct TowerTracker { 
+    towers: HashMap<Pubkey, Tower>,
+}
+
+impl TowerTracker { 
+    pub fn new() -> Self { 
+        TowerTracker::default()
+    }
+
+    pub fn get(&self, vote_account: &Pubkey) -> Option<&Tower> { 
+        self.towers.get(vote_account)
+    }
+
+    pub fn len(&self) -> usize { 
+        self.towers.len()
+    }
+
+    pub fn is_empty(&self) -> bool { 
+        self.towers.is_empty()
+    }
+
+    /// keeps the tower unless an older fetch of the account would replace a newer one
+    pub fn insert(&mut self, vote_account: Pubkey, tower: Tower) { 
+        match self.towers.get(&vote_account) { 
+            Some(current) if current.fetched_slot > tower.fetched_slot => {},
+            _ => { self.towers.insert(vote_account, tower); },
+        }
+    }
+
+    /// the vote accounts whose last vote (eg, from `getVoteAccounts`) is past what the tracked tower has
+    pub fn stale_accounts(&self, last_votes: &[(Pubkey, u64)]) -> Vec<Pubkey> { 
+        last_votes.iter()
+            .filter(|(vote_account, last_vote)| match self.towers.get(vote_account) { 
+                Some(tower) => tower.last_voted_slot().map_or(true, |slot| slot < *last_vote),
+                None => true,
+            })
+            .map(|(vote_account, _)| *vote_account)
+            .collect()
+    }
+
+    /// the vote accounts whose tower is more than `max_lag` slots behind the highest tower, with how far behind
+    pub fn lagging(&self, max_lag: u64) -> Vec<(Pubkey, u64)> { 
+        let highest = match self.towers.values().filter_map(|tower| tower.last_voted_slot()).max() { 
+            Some(highest) => highest,
+            None => return vec![],
+        };
+        let mut lagging = self.towers.iter()
+            .map(|(vote_account, tower)| (*vote_account, highest - tower.last_voted_slot().unwrap_or(0).min(highest)))
+            .filter(|(_, lag)| *lag > max_lag)
+            .collect::<Vec<_>>();
+        lagging.sort_by(|a, b| b.1.cmp(&a.1));
+        lagging
+    }
+
+    /// checks a vote from a block against its vote account's tower, None when theyre consistent
+    /// (or the account isnt tracked)
+    pub fn check_vote(&self, vote: &ParsedVote) -> Option<TowerInconsistency> { 
+        let tower = self.towers.get(&vote.vote_account)?;
+        // roots only move forward
+        if let (Some(vote_root), Some(on_chain_root)) = (vote.root, tower.root) { 
+            if tower.fetched_slot <= vote.slot && vote_root < on_chain_root { 
+                return Some(TowerInconsistency::RootRegressed { vote_root, on_chain_root });
+            }
+        }
+        // confirmations only go up once the vote lands (unless the validator switched forks)
+        if tower.fetched_slot > vote.slot { 
+            for (slot, voted) in vote.voted_slots.iter() { 
+                let on_chain = tower.lockouts.iter().find(|(x, _)| x == slot).map(|(_, count)| *count);
+                if let Some(on_chain) = on_chain { 
+                    if on_chain < *voted { 
+                        return Some(TowerInconsistency::LockoutMismatch { slot: *slot, on_chain, voted: *voted });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// refetches the vote accounts of the active set whose last vote moved since their tower was fetched,
+    /// returns how many towers were refreshed
+    pub async fn refresh_towers(&self) -> Result<usize, LightNodeError> { 
+        let vote_accounts = self.get_vote_accounts().await?;
+        let mut last_votes = vec![];
+        for info in vote_accounts.current.iter().chain(vote_accounts.delinquent.iter()) { 
+            let vote_account = info.vote_pubkey.parse::<Pubkey>()
+                .map_err(|e| LightNodeError::InvalidResponse(format!("invalid vote pubkey {}: {}", info.vote_pubkey, e)))?;
+            last_votes.push((vote_account, info.last_vote));
+        }
+        let stale = self.towers.read().unwrap().stale_accounts(&last_votes);
+
+        for chunk in stale.chunks(MAX_MULTIPLE_ACCOUNTS) { 
+            let (fetched_slot, accounts) = self.get_multiple_accounts(chunk).await?;
+            let mut towers = self.towers.write().unwrap();
+            for (vote_account, account) in chunk.iter().zip(accounts.iter()) { 
+                let account = match account { Some(account) => account, None => continue };
+                match Tower::from_account_data(&account.data, fetched_slot) { 
+                    Ok(tower) => towers.insert(*vote_account, tower),
+                    Err(e) => tracing::debug!("skipping vote account {}: {}", vote_account, e),
+                }
+            }
+        }
+        tracing::debug!("refreshed {} of {} towers", stale.len(), last_votes.len());
+
+        Ok(stale.len())
+    }
+
+    /// the tracked tower of `vote_account` (see `refresh_towers`)
+    pub fn tower(&self, vote_account: &Pubkey) -> Option<Tower> { 
+        self.towers.read().unwrap().get(vote_account).cloned()
+    }
+
+    /// the votes which dont line up with their voter's on-chain tower
+    pub fn check_votes_against_towers(&self, votes: &[ParsedVote]) -> Vec<(Pubkey, TowerInconsistency)> { 
+        let towers = self.towers.read().unwrap();
+        votes.iter()
+            .filter_map(|vote| towers.check_vote(vote).map(|x| (vote.vote_account, x)))
+            .collect()
+    }
+
+    /// the validators whose on-chain tower is more than `max_lag` slots behind
+    pub fn lagging_towers(&self, max_lag: u64) -> Vec<(Pubkey, u64)> { 
+        self.towers.read().unwrap().lagging(max_lag)
+    }
+}
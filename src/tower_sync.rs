@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use solana_sdk::{clock::Slot, hash::{Hash, HASH_BYTES}, vote::{instruction::VoteInstruction, state::{Lockout, VoteStateUpdate}}};
+
+// the vote program version we build against stops at `CompactUpdateVoteStateSwitch` (13), these are the
+// bincode tags of the instructions after it which validators send now
+pub const TOWER_SYNC_TAG: u32 = 14;
+pub const TOWER_SYNC_SWITCH_TAG: u32 = 15;
+
+/// a `TowerSync` / `TowerSyncSwitch` vote, decoded by hand
+#[derive(Debug, Clone, PartialEq)]
+pub struct TowerSync { 
+    // the tower, the same as a `CompactUpdateVoteState` carries
+    pub vote_state_update: VoteStateUpdate,
+    // id of the block voted on
+    pub block_id: Hash,
+    pub switch_proof_hash: Option<Hash>,
+}
+
+// the compact layout: root (u64::MAX for none), a short_vec of (varint slot offset, confirmation count),
+// hash, optional timestamp and block id, then the switch proof hash for the switch variant
+struct Reader<'a> { 
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> { 
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> { 
+        if self.data.len() < n { 
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(n);
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> { 
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> { 
+        self.take(4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> { 
+        self.take(8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Option<i64> { 
+        self.take(8).map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn hash(&mut self) -> Option<Hash> { 
+        self.take(HASH_BYTES).map(Hash::new)
+    }
+
+    // 7 bits a byte, low bits first, the high bit set on every byte but the last (`serde_varint`)
+    fn varint(&mut self) -> Option<u64> { 
+        let mut value = 0u64;
+        for i in 0..10 { 
+            let byte = self.u8()?;
+            // the 10th byte only has room for the top bit
+            if i == 9 && byte > 1 { 
+                return None;
+            }
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 { 
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    // the same encoding in at most 3 bytes (`short_vec`)
+    fn short_vec_len(&mut self) -> Option<usize> { 
+        let mut len = 0usize;
+        for i in 0..3 { 
+            let byte = self.u8()?;
+            len |= ((byte & 0x7f) as usize) << (7 * i);
+            if byte & 0x80 == 0 { 
+                return Some(len);
+            }
+        }
+        None
+    }
+}
+
+/// decodes the data of a `TowerSync` / `TowerSyncSwitch` vote ix, None for any other ix or truncated data
+pub fn decode_tower_sync(data: &[u8]) -> Option<TowerSync> { 
+    let mut reader = Reader { data };
+    let tag = reader.u32()?;
+    if tag != TOWER_SYNC_TAG && tag != TOWER_SYNC_SWITCH_TAG { 
+        return None;
+    }
+
+    let root = reader.u64()?;
+    let root = (root != Slot::MAX).then_some(root);
+    let num_lockouts = reader.short_vec_len()?;
+    // the lockout slots are offsets from the one before, the first from the root
+    let mut lockouts = VecDeque::new();
+    let mut slot = root.unwrap_or_default();
+    for _ in 0..num_lockouts { 
+        slot = slot.checked_add(reader.varint()?)?;
+        let confirmation_count = reader.u8()?;
+        lockouts.push_back(Lockout::new_with_confirmation_count(slot, confirmation_count as u32));
+    }
+    let hash = reader.hash()?;
+    let timestamp = match reader.u8()? { 
+        0 => None,
+        1 => Some(reader.i64()?),
+        _ => return None,
+    };
+    let block_id = reader.hash()?;
+    let switch_proof_hash = match tag { 
+        TOWER_SYNC_SWITCH_TAG => Some(reader.hash()?),
+        _ => None,
+    };
+
+    Some(TowerSync { 
+        vote_state_update: VoteStateUpdate { lockouts, root, hash, timestamp },
+        block_id,
+        switch_proof_hash,
+    })
+}
+
+/// the vote ix in `data`, None when it isnt one we can decode. a tower sync comes back as the
+/// `CompactUpdateVoteState(Switch)` with the same tower (everything but the block id) so the rest of
+/// the vote parsing handles it like the older instructions
+pub fn decode_vote_instruction(data: &[u8]) -> Option<VoteInstruction> { 
+    if let Ok(vote_ix) = bincode::deserialize::<VoteInstruction>(data) { 
+        return Some(vote_ix);
+    }
+    let tower_sync = decode_tower_sync(data)?;
+    Some(match tower_sync.switch_proof_hash { 
+        Some(hash) => VoteInstruction::CompactUpdateVoteStateSwitch(tower_sync.vote_state_update, hash),
+        None => VoteInstruction::CompactUpdateVoteState(tower_sync.vote_state_update),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests { 
+    use super::*;
+
+    // TowerSync { root: 1000, lockouts: [(1200, 4), (1202, 3), (1203, 2), (1210, 1)], hash: [0x11; 32],
+    // timestamp: 1700000000, block_id: [0x22; 32] } as validators serialize it
+    pub(crate) fn tower_sync_data(switch_proof_hash: Option<Hash>) -> Vec<u8> { 
+        let tag = if switch_proof_hash.is_some() { TOWER_SYNC_SWITCH_TAG } else { TOWER_SYNC_TAG };
+        let mut data = tag.to_le_bytes().to_vec();
+        data.extend(1000u64.to_le_bytes());
+        // 4 lockouts: +200 (a 2 byte varint) with 4 confirmations, +2 / 3, +1 / 2, +7 / 1
+        data.extend([4, 0xc8, 0x01, 4, 2, 3, 1, 2, 7, 1]);
+        data.extend([0x11; 32]);
+        data.push(1);
+        data.extend(1_700_000_000i64.to_le_bytes());
+        data.extend([0x22; 32]);
+        if let Some(hash) = switch_proof_hash { 
+            data.extend(hash.to_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_decode_tower_sync() { 
+        let data = tower_sync_data(None);
+        assert_eq!(data.len(), 95);
+
+        let tower_sync = decode_tower_sync(&data).unwrap();
+        let v = &tower_sync.vote_state_update;
+        assert_eq!(v.root, Some(1000));
+        let lockouts = v.lockouts.iter().map(|x| (x.slot(), x.confirmation_count())).collect::<Vec<_>>();
+        assert_eq!(lockouts, vec![(1200, 4), (1202, 3), (1203, 2), (1210, 1)]);
+        assert_eq!(v.hash, Hash::new(&[0x11; 32]));
+        assert_eq!(v.timestamp, Some(1_700_000_000));
+        assert_eq!(tower_sync.block_id, Hash::new(&[0x22; 32]));
+        assert_eq!(tower_sync.switch_proof_hash, None);
+    }
+
+    #[test]
+    fn test_decode_tower_sync_switch() { 
+        let switch_proof_hash = Hash::new(&[0x33; 32]);
+        let tower_sync = decode_tower_sync(&tower_sync_data(Some(switch_proof_hash))).unwrap();
+        assert_eq!(tower_sync.switch_proof_hash, Some(switch_proof_hash));
+        assert_eq!(tower_sync.vote_state_update.last_voted_slot(), Some(1210));
+    }
+
+    #[test]
+    fn test_decode_tower_sync_without_root_or_timestamp() { 
+        let mut data = TOWER_SYNC_TAG.to_le_bytes().to_vec();
+        data.extend(u64::MAX.to_le_bytes());
+        // without a root the first offset is from slot 0
+        data.extend([1, 0xe8, 0x07, 1]);
+        data.extend([0x11; 32]);
+        data.push(0);
+        data.extend([0x22; 32]);
+
+        let v = decode_tower_sync(&data).unwrap().vote_state_update;
+        assert_eq!(v.root, None);
+        assert_eq!(v.last_voted_slot(), Some(1000));
+        assert_eq!(v.timestamp, None);
+    }
+
+    #[test]
+    fn test_decode_tower_sync_truncated() { 
+        let data = tower_sync_data(Some(Hash::new(&[0x33; 32])));
+        for len in 0..data.len() { 
+            assert_eq!(decode_tower_sync(&data[..len]), None, "decoded {} bytes", len);
+        }
+        // another vote ix
+        let mut data = tower_sync_data(None);
+        data[0] = 12;
+        assert_eq!(decode_tower_sync(&data), None);
+    }
+
+    #[test]
+    fn test_decode_vote_instruction() { 
+        let switch_proof_hash = Hash::new(&[0x33; 32]);
+        match decode_vote_instruction(&tower_sync_data(None)) { 
+            Some(VoteInstruction::CompactUpdateVoteState(v)) => assert_eq!(v.last_voted_slot(), Some(1210)),
+            vote_ix => panic!("unexpected {:?}", vote_ix),
+        }
+        match decode_vote_instruction(&tower_sync_data(Some(switch_proof_hash))) { 
+            Some(VoteInstruction::CompactUpdateVoteStateSwitch(v, hash)) => { 
+                assert_eq!(v.last_voted_slot(), Some(1210));
+                assert_eq!(hash, switch_proof_hash);
+            }
+            vote_ix => panic!("unexpected {:?}", vote_ix),
+        }
+        assert_eq!(decode_vote_instruction(&[0xff; 8]), None);
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use reqwest::{StatusCode, header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER}};
+use thiserror::Error;
+
+use crate::config::DEFAULT_REQUEST_TIMEOUT;
+
+#[derive(Debug, Error)]
+pub enum TransportError { 
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("http status {0}")]
+    Status(u16),
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("rate limited by the endpoint (retry after {0:?})")]
+    RateLimited(Option<Duration>),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl TransportError { 
+    /// connection errors, timeouts, 429s and 5xx responses are worth retrying
+    pub fn is_transient(&self) -> bool { 
+        match self { 
+            TransportError::Http(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+            TransportError::Status(status) => *status >= 500,
+            TransportError::Timeout(_) | TransportError::RateLimited(_) => true,
+            TransportError::Other(_) => false,
+        }
+    }
+}
+
+/// sends raw JSON-RPC request bodies to an endpoint
+/// (eg, over http, through a proxy, or canned responses)
+#[async_trait]
+pub trait RpcTransport: Send + Sync { 
+    async fn send(&self, body: String) -> Result<String, TransportError>;
+}
+
+// clients are cheap to clone and clones share the pool, so every client using the default
+// timeout (eg, the ones built by the one-off helpers) shares this one
+static DEFAULT_HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| new_http_client(DEFAULT_REQUEST_TIMEOUT));
+
+/// `timeout` bounds each request, from connecting until the body is read
+pub fn build_http_client(timeout: Duration) -> reqwest::Client { 
+    if timeout == DEFAULT_REQUEST_TIMEOUT { 
+        return DEFAULT_HTTP_CLIENT.clone();
+    }
+    new_http_client(timeout)
+}
+
+fn new_http_client(timeout: Duration) -> reqwest::Client { 
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_keepalive(Duration::from_secs(60))
+        .build()
+        .expect("failed to build http client")
+}
+
+// one pooled client is held per transport so connections (and tls sessions) are reused across calls
+pub struct HttpTransport { 
+    pub endpoint: String,
+    client: reqwest::Client,
+}
+
+impl HttpTransport { 
+    pub fn new(endpoint: &str) -> Self { 
+        HttpTransport::with_timeout(endpoint, DEFAULT_REQUEST_TIMEOUT)
+    }
+
+    /// `timeout` bounds each request, from connecting until the body is read
+    pub fn with_timeout(endpoint: &str, timeout: Duration) -> Self { 
+        HttpTransport::with_client(endpoint, build_http_client(timeout))
+    }
+
+    /// reuses an existing client (eg, shared between multiple transports)
+    pub fn with_client(endpoint: &str, client: reqwest::Client) -> Self { 
+        HttpTransport { 
+            endpoint: endpoint.to_string(),
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl RpcTransport for HttpTransport { 
+    async fn send(&self, body: String) -> Result<String, TransportError> { 
+        let res = self.client
+            .post(&self.endpoint)
+            .body(body)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .send()
+            .await?;
+        let status = res.status();
+        if status == StatusCode::TOO_MANY_REQUESTS { 
+            // only the delay-seconds form of the header is supported
+            let retry_after = res.headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(TransportError::RateLimited(retry_after));
+        }
+        if status.is_server_error() { 
+            return Err(TransportError::Status(status.as_u16()));
+        }
+        Ok(res.text().await?)
+    }
+}
@@ -0,0 +1,533 @@
+use std::{str::FromStr, time::{Duration, Instant}};
+
+use futures::{StreamExt, stream};
+use serde::{Serialize, Deserialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{BlockHeader, EntryProof, TransactionDetails, UiConfirmedBlock};
+use solana_sdk::hash::Hash;
+
+use crate::bank_hash::bank_hash;
+use crate::client::LightClient;
+use crate::entries::{verify_tx_proof, verify_entries_blocking, verify_ticks};
+use crate::error::LightNodeError;
+use crate::rpc::GetBlockOptions;
+use crate::transport::RpcTransport;
+use crate::stake::{Threshold, total_stake};
+use crate::vote::{detect_clock_drift, stake_for_bank_hash, stake_weighted_timestamp};
+
+// blocks (starting at the verified one) whose votes are counted
+pub(crate) const VOTE_SLOTS_AHEAD: u64 = 5;
+// how far behind the rpc's latest slot `verify_latest_slot` starts, by then the votes on it have landed
+pub const LATEST_SLOT_MARGIN: u64 = VOTE_SLOTS_AHEAD;
+
+// the headers can be fetched through any tx in the block
+fn first_signature(block: &UiConfirmedBlock) -> Result<Option<Signature>, LightNodeError> { 
+    match block.signatures.as_ref().and_then(|sigs| sigs.first()) { 
+        Some(sig) => Signature::from_str(sig)
+            .map(Some)
+            .map_err(|_| LightNodeError::InvalidResponse(format!("invalid signature {}", sig))),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn decode_block_headers(slot: u64, block_headers: &[u8]) -> Result<BlockHeader, LightNodeError> { 
+    if block_headers.is_empty() { 
+        return Err(LightNodeError::EmptyBlockHeaders(slot));
+    }
+    bincode::deserialize(block_headers).map_err(|source| LightNodeError::InvalidBlockHeaders { slot, source })
+}
+
+// a PoH path can hash correctly from the start and still end somewhere other than the blockhash, or
+// have the wrong tick structure. the failure kind and reason of `entries` (which hash from the
+// previous blockhash) when they do
+fn check_entries_end(slot: u64, entries: &[EntryProof], blockhash: &Hash, ticks_per_slot: u64, hashes_per_tick: Option<u64>) -> Option<(&'static str, String)> { 
+    if let Some(computed) = entries.last().map(|entry| entry.hash()) { 
+        if computed != *blockhash { 
+            return Some(("blockhash", LightNodeError::BlockhashMismatch { slot, computed, blockhash: *blockhash }.to_string()));
+        }
+    }
+    verify_ticks(entries, ticks_per_slot, hashes_per_tick, blockhash).err().map(|e| ("ticks", e.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationResult { 
+    pub slot: u64,
+    pub last_blockhash: Option<Hash>,
+    pub bank_hash: Option<Hash>,
+    // the scheduled leader, once its been checked against the block's producer (None with `verify_leader`
+    // off or when the producer isnt known)
+    pub leader: Option<Pubkey>,
+    pub num_entries: usize,
+    // entries which contain transactions (the headers dont include the txs themselves)
+    pub num_tx_entries: usize,
+    pub voted_stake: u128,
+    pub total_stake: u128,
+    // fraction of total_stake voted_stake had to reach
+    pub threshold: Threshold,
+    // stake weighted median of the vote timestamps and the rpc's `getBlockTime`
+    pub vote_timestamp: Option<i64>,
+    pub block_time: Option<i64>,
+    // set when the two are further apart than `max_block_time_drift`, doesnt fail the verification
+    pub block_time_drifted: bool,
+    pub verified: bool,
+    pub failure_reason: Option<String>,
+    // metrics label of the check which failed (eg, "poh")
+    #[serde(skip)]
+    failure_kind: Option<&'static str>,
+}
+
+impl VerificationResult { 
+    fn new(slot: u64) -> Self { 
+        VerificationResult { 
+            slot,
+            last_blockhash: None,
+            bank_hash: None,
+            leader: None,
+            num_entries: 0,
+            num_tx_entries: 0,
+            voted_stake: 0,
+            total_stake: 0,
+            threshold: Threshold::SUPERMAJORITY,
+            vote_timestamp: None,
+            block_time: None,
+            block_time_drifted: false,
+            verified: false,
+            failure_reason: None,
+            failure_kind: None,
+        }
+    }
+
+    fn fail(mut self, kind: &'static str, reason: String) -> Self { 
+        tracing::warn!(slot = self.slot, kind, "verification failed: {}", reason);
+        self.verified = false;
+        self.failure_reason = Some(reason);
+        self.failure_kind = Some(kind);
+        self
+    }
+
+    pub fn failure_kind(&self) -> Option<&'static str> { 
+        self.failure_kind
+    }
+
+    pub fn summary(&self) -> VerificationSummary { 
+        VerificationSummary { 
+            slot: self.slot,
+            verified: self.verified,
+            entries_verified: self.last_blockhash.is_some(),
+            producer_ok: self.leader.is_some(),
+            confirmed_stake_pct: stake_pct(self.voted_stake, self.total_stake),
+            threshold: self.threshold.as_f64(),
+            failure_reason: self.failure_reason.clone(),
+        }
+    }
+}
+
+/// the verdict of a `VerificationResult` (eg, for `--output json` and the http api)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerificationSummary { 
+    pub slot: u64,
+    pub verified: bool,
+    // the PoH path and tick structure checked out
+    pub entries_verified: bool,
+    // the block was produced by the scheduled leader (false when the check is off or couldnt be done)
+    pub producer_ok: bool,
+    pub confirmed_stake_pct: f64,
+    pub threshold: f64,
+    pub failure_reason: Option<String>,
+}
+
+pub fn stake_pct(stake: u128, total_stake: u128) -> f64 { 
+    if total_stake == 0 { 
+        0.0
+    } else { 
+        100.0 * stake as f64 / total_stake as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainVerificationResult { 
+    pub start_slot: u64,
+    pub end_slot: u64,
+    // slots with a block which chained onto the previous one (skipped slots arent included)
+    pub verified_slots: Vec<u64>,
+    // first slot where the chain breaks
+    pub broken_at: Option<u64>,
+    pub failure_reason: Option<String>,
+}
+
+impl ChainVerificationResult { 
+    fn new(start_slot: u64, end_slot: u64) -> Self { 
+        ChainVerificationResult { 
+            start_slot,
+            end_slot,
+            verified_slots: vec![],
+            broken_at: None,
+            failure_reason: None,
+        }
+    }
+
+    fn break_at(mut self, slot: u64, reason: String) -> Self { 
+        self.broken_at = Some(slot);
+        self.failure_reason = Some(reason);
+        self
+    }
+
+    pub fn verified(&self) -> bool { 
+        self.broken_at.is_none()
+    }
+}
+
+/// `verify_block` of every block in a range
+#[derive(Debug)]
+pub struct RangeVerification { 
+    pub start_slot: u64,
+    pub end_slot: u64,
+    // slots in the range with a block
+    pub num_blocks: usize,
+    // in slot order, verified or not
+    pub results: Vec<VerificationResult>,
+    // slots whose verification errored out instead of returning a result (eg, the rpc failed)
+    pub errors: Vec<(u64, LightNodeError)>,
+    pub elapsed: Duration,
+}
+
+impl RangeVerification { 
+    pub fn num_verified(&self) -> usize { 
+        self.results.iter().filter(|result| result.verified).count()
+    }
+
+    /// the results which didnt verify
+    pub fn failed(&self) -> impl Iterator<Item = &VerificationResult> { 
+        self.results.iter().filter(|result| !result.verified)
+    }
+}
+
+/// proof that a tx was recorded into the PoH of a slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof { 
+    pub slot: u64,
+    pub signature: Signature,
+    // index of the entry whose merkle root contains the tx
+    pub entry_index: usize,
+    // the hash the slot's PoH started from (the previous blockhash)
+    pub start_blockhash: Hash,
+    // hash of every entry from the tx's entry to the last one, the last one is the blockhash
+    pub entry_hashes: Vec<Hash>,
+    pub blockhash: Hash,
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    async fn get_decoded_block_headers(&self, slot: u64, tx_sig: Signature) -> Result<BlockHeader, LightNodeError> { 
+        decode_block_headers(slot, &self.get_block_headers(slot, tx_sig).await?)
+    }
+
+    #[tracing::instrument(skip(self, tx_sig))]
+    pub async fn verify_slot(&self, slot: u64, tx_sig: Signature) -> Result<VerificationResult, LightNodeError> { 
+        let start = Instant::now();
+        let result = self.verify_slot_unmetered(slot, tx_sig).await;
+        self.metrics.record_verification(&result, start.elapsed());
+        result
+    }
+
+    async fn verify_slot_unmetered(&self, slot: u64, tx_sig: Signature) -> Result<VerificationResult, LightNodeError> { 
+        let mut result = VerificationResult::new(slot);
+        result.threshold = self.confirmation_threshold;
+
+        // get headers
+        let block_headers = self.get_decoded_block_headers(slot, tx_sig).await?;
+        let entries = block_headers.entries; 
+        result.num_entries = entries.len();
+        result.num_tx_entries = entries.iter()
+            .filter(|entry| match entry { 
+                EntryProof::PartialEntry(x) => x.transaction_hash.is_some(),
+                EntryProof::MerkleEntry(_) => true,
+            })
+            .count();
+
+        // find and verify tx signature in entry
+        match verify_tx_proof(&entries, &tx_sig) { 
+            Some(true) => {},
+            Some(false) => return Ok(result.fail("tx_proof", format!("merkle proof for tx {} is invalid", tx_sig))),
+            None => return Ok(result.fail("tx_proof", format!("tx {} not found in entries", tx_sig))),
+        }
+
+        // verify the entries are valid PoH ticks / path 
+        let (entries, poh_verified) = verify_entries_blocking(block_headers.start_blockhash, entries, tx_sig).await?;
+        if !poh_verified { 
+            return Ok(result.fail("poh", "invalid PoH entries".to_string()));
+        }
+
+        let blockhash = self.get_blockhash(slot).await?;
+        if let Some((kind, reason)) = check_entries_end(slot, &entries, &blockhash, self.ticks_per_slot, self.hashes_per_tick) { 
+            return Ok(result.fail(kind, reason));
+        }
+
+        // a block from an unscheduled identity fails, one whose producer cant be found is left unchecked
+        if self.verify_leader { 
+            match self.verify_block_leader(slot).await { 
+                Ok(leader) => result.leader = leader,
+                Err(e @ LightNodeError::UnexpectedLeader { .. }) => return Ok(result.fail("leader", e.to_string())),
+                Err(e) => return Err(e),
+            }
+        }
+
+        // recompute the bank hash 
+        let last_blockhash = match entries.last() { 
+            Some(entry) => entry.hash(),
+            None => return Ok(result.fail("no_entries", "no entries in block".to_string())),
+        };
+        let bankhash = bank_hash(
+            &block_headers.parent_hash,
+            &block_headers.accounts_delta_hash,
+            &block_headers.signature_count_buf,
+            &last_blockhash,
+        );
+        result.last_blockhash = Some(last_blockhash);
+        result.bank_hash = Some(bankhash);
+
+        let votes = self.parse_block_votes(slot, VOTE_SLOTS_AHEAD).await?;
+        if votes.is_empty() { 
+            return Ok(result.fail("no_votes", "no votes found".to_string()));
+        }
+        let total_stake = total_stake(&self.stakes_for_slot(slot).await?.stakes);
+        let bankhash_vote_stakes = stake_for_bank_hash(&votes, &bankhash);
+        result.voted_stake = bankhash_vote_stakes;
+        result.total_stake = total_stake;
+
+        // the votes give a clock estimate independent of the rpc's block time
+        result.vote_timestamp = stake_weighted_timestamp(&votes, slot);
+        result.block_time = self.get_block_time(slot).await?;
+        if let Some(block_time) = result.block_time { 
+            let drifted = detect_clock_drift(&votes, slot, block_time, self.max_block_time_drift);
+            if !drifted.is_empty() { 
+                tracing::debug!("{} voters of slot {} have a timestamp off from the block time: {:?}", drifted.len(), slot, drifted);
+            }
+        }
+        if let (Some(vote_timestamp), Some(block_time)) = (result.vote_timestamp, result.block_time) { 
+            let drift = vote_timestamp.abs_diff(block_time);
+            if drift > self.max_block_time_drift.as_secs() { 
+                tracing::debug!("block time of slot {} is {}s from the vote timestamps", slot, drift);
+                result.block_time_drifted = true;
+            }
+        }
+
+        if !self.confirmation_threshold.is_met(bankhash_vote_stakes, total_stake) { 
+            return Ok(result.fail("supermajority", format!("bank hash does not have {} of the stake", self.confirmation_threshold)));
+        }
+
+        // the votes on later slots are checked against this hash too
+        self.slot_hashes.write().unwrap().insert_verified(slot, bankhash);
+        result.verified = true;
+        tracing::info!(voted_stake = result.voted_stake, total_stake = result.total_stake, "verified slot {}", slot);
+        Ok(result)
+    }
+
+    /// checks the tx is in the merkle root of one of the slot's entries and that the entries
+    /// hash from the previous blockhash through to the slot's blockhash
+    pub async fn verify_tx_inclusion(&self, sig: Signature, slot: u64) -> Result<InclusionProof, LightNodeError> { 
+        let block_headers = self.get_decoded_block_headers(slot, sig).await?;
+        let start_blockhash = block_headers.start_blockhash;
+
+        match verify_tx_proof(&block_headers.entries, &sig) { 
+            Some(true) => {},
+            Some(false) => return Err(LightNodeError::Verification(format!("merkle proof for tx {} is invalid", sig))),
+            None => return Err(LightNodeError::Verification(format!("tx {} not found in entries", sig))),
+        }
+        let entry_index = block_headers.entries.iter()
+            .position(|entry| matches!(entry, EntryProof::MerkleEntry(_)))
+            .ok_or_else(|| LightNodeError::Verification(format!("tx {} not found in entries", sig)))?;
+        let (entries, poh_verified) = verify_entries_blocking(start_blockhash, block_headers.entries, sig).await?;
+        if !poh_verified { 
+            return Err(LightNodeError::Verification("invalid PoH entries".to_string()));
+        }
+
+        let blockhash = self.get_blockhash(slot).await?;
+        let entry_hashes = entries[entry_index..].iter().map(|entry| entry.hash()).collect::<Vec<_>>();
+        match entry_hashes.last() { 
+            Some(computed) if *computed != blockhash => { 
+                return Err(LightNodeError::BlockhashMismatch { slot, computed: *computed, blockhash });
+            }
+            Some(_) => {},
+            None => return Err(LightNodeError::Verification(format!("entries dont end at the blockhash {}", blockhash))),
+        }
+
+        Ok(InclusionProof { 
+            slot,
+            signature: sig,
+            entry_index,
+            start_blockhash,
+            entry_hashes,
+            blockhash,
+        })
+    }
+
+    /// `verify_slot` but gives up with `Timeout` once `deadline` has passed
+    pub async fn verify_slot_with_deadline(&self, slot: u64, tx_sig: Signature, deadline: Duration) -> Result<VerificationResult, LightNodeError> { 
+        tokio::time::timeout(deadline, self.verify_slot(slot, tx_sig)).await
+            .map_err(|_| LightNodeError::Timeout(deadline))?
+    }
+
+    /// `verify_slot` through the first tx of the block, waiting for the block if its not available yet
+    /// (up to `block_wait_timeout`, then `Timeout`)
+    pub async fn verify_block(&self, slot: u64) -> Result<VerificationResult, LightNodeError> { 
+        let options = GetBlockOptions::new(self.encoding).transaction_details(TransactionDetails::Signatures).rewards(false);
+        let block = self.wait_for_block_with_options(slot, &options).await?;
+        match first_signature(&block)? { 
+            Some(tx_sig) => self.verify_slot(slot, tx_sig).await,
+            None => Ok(VerificationResult::new(slot).fail("no_txs", "block has no txs to fetch its headers with".to_string())),
+        }
+    }
+
+    /// `verify_block` of the latest slot at the client's commitment (less `LATEST_SLOT_MARGIN`)
+    pub async fn verify_latest_slot(&self) -> Result<VerificationResult, LightNodeError> { 
+        let slot = self.get_slot().await?.saturating_sub(LATEST_SLOT_MARGIN);
+        self.verify_block(slot).await
+    }
+
+    /// `verify_block` of `slot`, or `verify_latest_slot` when its None
+    pub async fn verify(&self, slot: Option<u64>) -> Result<VerificationResult, LightNodeError> { 
+        match slot { 
+            Some(slot) => self.verify_block(slot).await,
+            None => self.verify_latest_slot().await,
+        }
+    }
+
+    /// verifies the PoH of every block in `start..=end` and that each block continues from
+    /// the blockhash of the block before it, stopping at the first slot where the chain breaks
+    pub async fn verify_slot_chain(&self, start: u64, end: u64) -> Result<ChainVerificationResult, LightNodeError> { 
+        let mut result = ChainVerificationResult::new(start, end);
+        // slot and blockhash of the last verified block
+        let mut parent: Option<(u64, Hash)> = None;
+
+        for slot in start..=end { 
+            let block = match self.get_block_with_details(slot, TransactionDetails::Signatures).await { 
+                Ok(block) => block,
+                Err(LightNodeError::SlotSkipped(_)) => continue,
+                Err(e) => return Err(e),
+            };
+
+            let tx_sig = match first_signature(&block)? { 
+                Some(tx_sig) => tx_sig,
+                None => return Ok(result.break_at(slot, "block has no txs to fetch its headers with".to_string())),
+            };
+            let block_headers = self.get_decoded_block_headers(slot, tx_sig).await?;
+            let start_blockhash = block_headers.start_blockhash;
+
+            let (entries, poh_verified) = verify_entries_blocking(start_blockhash, block_headers.entries, tx_sig).await?;
+            if !poh_verified { 
+                return Ok(result.break_at(slot, "invalid PoH entries".to_string()));
+            }
+            let blockhash = match entries.last() { 
+                Some(entry) => entry.hash(),
+                None => return Ok(result.break_at(slot, "no entries in block".to_string())),
+            };
+            if blockhash.to_string() != block.blockhash { 
+                return Ok(result.break_at(slot, format!("last entry {} doesnt match the blockhash {}", blockhash, block.blockhash)));
+            }
+            if start_blockhash.to_string() != block.previous_blockhash { 
+                return Ok(result.break_at(slot, format!(
+                    "PoH starts from {} but the previous blockhash is {}", start_blockhash, block.previous_blockhash
+                )));
+            }
+
+            if let Some((parent_slot, parent_blockhash)) = parent { 
+                if block.parent_slot != parent_slot { 
+                    return Ok(result.break_at(slot, format!(
+                        "parent slot is {} but the previous block in the range is {}", block.parent_slot, parent_slot
+                    )));
+                }
+                if start_blockhash != parent_blockhash { 
+                    return Ok(result.break_at(slot, format!(
+                        "PoH starts from {} instead of the parent blockhash {}", start_blockhash, parent_blockhash
+                    )));
+                }
+            }
+
+            result.verified_slots.push(slot);
+            parent = Some((slot, blockhash));
+        }
+
+        Ok(result)
+    }
+
+    /// verifies every block `getBlocks` lists in `start..=end`, `concurrency` at a time, a slot which fails
+    /// (or errors) doesnt stop the rest of the range
+    pub async fn verify_range(&self, start: u64, end: u64, concurrency: usize) -> Result<RangeVerification, LightNodeError> { 
+        let started = Instant::now();
+        let slots = self.get_confirmed_blocks(start, end).await?;
+        let num_blocks = slots.len();
+
+        let mut results = vec![];
+        let mut errors = vec![];
+        let mut verified = stream::iter(slots)
+            .map(|slot| async move { (slot, self.verify_block(slot).await) })
+            .buffered(concurrency.max(1));
+        while let Some((slot, result)) = verified.next().await { 
+            match result { 
+                Ok(result) => results.push(result),
+                // listed by `getBlocks` but the block is gone (eg, the node purged it)
+                Err(LightNodeError::SlotSkipped(_)) => {},
+                Err(e) => errors.push((slot, e)),
+            }
+        }
+
+        Ok(RangeVerification { 
+            start_slot: start,
+            end_slot: end,
+            num_blocks,
+            results,
+            errors,
+            elapsed: started.elapsed(),
+        })
+    }
+}
+
+pub async fn verify_slot(endpoint: &str, slot: u64, tx_sig: Signature) -> Result<VerificationResult, LightNodeError> { 
+    LightClient::new(endpoint).verify_slot(slot, tx_sig).await
+}
+
+pub async fn verify_slot_chain(endpoint: &str, start: u64, end: u64) -> Result<ChainVerificationResult, LightNodeError> { 
+    LightClient::new(endpoint).verify_slot_chain(start, end).await
+}
+
+#[cfg(test)]
+mod tests { 
+    use solana_sdk::hash::hashv;
+    use solana_transaction_status::PartialEntry;
+
+    use super::*;
+    use crate::entries::{next_hash_with_tx_hash, verify_entries};
+
+    // 4 ticks of 10 hashes with a tx entry in each, from `start_blockhash`
+    fn entries(start_blockhash: &Hash) -> Vec<EntryProof> { 
+        let mut hash = *start_blockhash;
+        let mut entries = vec![];
+        for tick in 0..4u64 { 
+            for (num_hashes, transaction_hash) in [(3, Some(hashv(&[&tick.to_le_bytes()]))), (7, None)] { 
+                hash = next_hash_with_tx_hash(&hash, num_hashes, transaction_hash);
+                entries.push(EntryProof::PartialEntry(PartialEntry { num_hashes, hash, transaction_hash }));
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn test_entries_end_at_another_blockhash() { 
+        let start_blockhash = hashv(&[b"previous blockhash"]);
+        let entries = entries(&start_blockhash);
+        let last_hash = entries.last().unwrap().hash();
+        assert!(verify_entries(&start_blockhash, &entries, &Signature::default()));
+        assert_eq!(check_entries_end(42, &entries, &last_hash, 4, Some(10)), None);
+
+        // the path hashes fine from the start but the block says it ends somewhere else
+        let blockhash = hashv(&[b"blockhash"]);
+        let (kind, reason) = check_entries_end(42, &entries, &blockhash, 4, Some(10)).unwrap();
+        let result = VerificationResult::new(42).fail(kind, reason);
+        assert!(!result.verified);
+        assert_eq!(result.failure_kind(), Some("blockhash"));
+
+        // ending at the blockhash with the wrong number of ticks is a tick failure instead
+        let (kind, _) = check_entries_end(42, &entries, &last_hash, 64, Some(10)).unwrap();
+        assert_eq!(kind, "ticks");
+    }
+}
@@ -0,0 +1,895 @@
+use std::{collections::{HashMap, HashSet}, str::FromStr, sync::Arc, time::Duration};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::{Serialize, Deserialize};
+use solana_sdk::{vote::{instruction::VoteInstruction, state::VoteState, self}, transaction::VersionedTransaction, pubkey::Pubkey, signature::Signature};
+use solana_sdk::{instruction::CompiledInstruction, message::{legacy, v0, MessageAddressTableLookup, VersionedMessage}};
+use solana_transaction_status::{EncodedTransaction, TransactionBinaryEncoding, UiMessage, UiTransaction};
+use solana_sdk::hash::Hash;
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::lookup_table::MAX_MULTIPLE_ACCOUNTS;
+use crate::rpc::GetBlockOptions;
+use crate::sigverify::verify_transactions_batch;
+use crate::stake::{StakeInfo, Threshold, total_stake};
+use crate::tower_sync::decode_vote_instruction;
+use crate::transport::RpcTransport;
+use crate::vote_tracker::RootTracker;
+
+// blocks before the slot whose votes are used for its time estimate
+const CLUSTER_TIME_SLOTS: u64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteSource { 
+    // parsed out of a vote tx in a block
+    Block,
+    // a `voteSubscribe` notification
+    Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedVote { 
+    // None when the vote account isnt in the stake map (its stake counts as zero)
+    pub node_pubkey: Option<Pubkey>,
+    pub vote_account: Pubkey,
+    // last slot in the vote
+    pub slot: u64,
+    pub bank_hash: Hash,
+    pub stake: u64,
+    pub signature_verified: bool,
+    // the vote ix was signed by the vote account's authorized voter (not just any signer of the tx)
+    pub voter_authorized: bool,
+    pub timestamp: Option<i64>,
+    // hash of the switching proof when the vote switched forks
+    pub switch_proof_hash: Option<Hash>,
+    // (slot, confirmation count) of every slot in the voter's tower, a vote on `slot` also votes on these
+    pub voted_slots: Vec<(u64, u32)>,
+    // root of the voter's tower, plain `Vote`s dont carry one
+    pub root: Option<u64>,
+    // the slot / bank hash contradicts the known slot hashes (see `LightClient::take_suspicious_votes`)
+    pub slot_hash_mismatch: bool,
+    // the signed tx the vote came from (block votes only)
+    pub signed_vote: Option<Arc<SignedVote>>,
+    pub source: VoteSource,
+}
+
+/// what a third party needs to check a vote was signed: the signatures over the serialized message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedVote { 
+    pub signatures: Vec<Signature>,
+    pub message: Vec<u8>,
+    // a tower sync is kept as the `CompactUpdateVoteState(Switch)` with its tower, the original ix is in `message`
+    pub vote_instruction: VoteInstruction,
+}
+
+impl ParsedVote { 
+    /// whether the vote counts towards the stake of its slot / bank hash:
+    /// block votes need a valid signature from the authorized voter and a bank hash which matches the slot hashes, subscription votes come without their tx so
+    /// they cant be checked here (the node verifies them before relaying)
+    pub fn counts(&self) -> bool { 
+        match self.source { 
+            VoteSource::Block => self.signature_verified && self.voter_authorized && !self.slot_hash_mismatch,
+            VoteSource::Subscription => true,
+        }
+    }
+}
+
+pub fn decode_transaction(tx: &EncodedTransaction) -> Result<VersionedTransaction, LightNodeError> { 
+    let tx = match tx { 
+        EncodedTransaction::Binary(tx, TransactionBinaryEncoding::Base58) => bs58::decode(tx).into_vec()?,
+        EncodedTransaction::Binary(tx, TransactionBinaryEncoding::Base64) => BASE64.decode(tx)?,
+        // legacy binary is always base58
+        EncodedTransaction::LegacyBinary(tx) => bs58::decode(tx).into_vec()?,
+        EncodedTransaction::Json(tx) => return transaction_from_json(tx),
+        // `transactionDetails: accounts` drops the instructions
+        EncodedTransaction::Accounts(_) => return Err(LightNodeError::UnsupportedEncoding("accounts, expected the full transactions".to_string())),
+    };
+    Ok(bincode::deserialize(&tx[..])?)
+}
+
+/// rebuilds the signed tx from its `json` encoding, the raw message has everything the binary one does
+/// (v0 messages are the ones with `addressTableLookups`, even an empty list)
+pub fn transaction_from_json(tx: &UiTransaction) -> Result<VersionedTransaction, LightNodeError> { 
+    let msg = match &tx.message { 
+        UiMessage::Raw(msg) => msg,
+        // the instructions of known programs are parsed without their data so the signed message cant be rebuilt
+        UiMessage::Parsed(_) => return Err(LightNodeError::UnsupportedEncoding("jsonParsed, expected json or a binary encoding".to_string())),
+    };
+    let signatures = tx.signatures.iter()
+        .map(|x| Signature::from_str(x).map_err(|e| LightNodeError::InvalidResponse(format!("invalid signature {}: {}", x, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let account_keys = msg.account_keys.iter()
+        .map(|x| Pubkey::from_str(x))
+        .collect::<Result<Vec<_>, _>>()?;
+    let recent_blockhash = Hash::from_str(&msg.recent_blockhash)
+        .map_err(|e| LightNodeError::InvalidResponse(format!("invalid blockhash {}: {}", msg.recent_blockhash, e)))?;
+    let instructions = msg.instructions.iter()
+        .map(|ix| Ok(CompiledInstruction { 
+            program_id_index: ix.program_id_index,
+            accounts: ix.accounts.clone(),
+            data: bs58::decode(&ix.data).into_vec()?,
+        }))
+        .collect::<Result<Vec<_>, LightNodeError>>()?;
+
+    let message = match &msg.address_table_lookups { 
+        None => VersionedMessage::Legacy(legacy::Message { 
+            header: msg.header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        }),
+        Some(lookups) => VersionedMessage::V0(v0::Message { 
+            header: msg.header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups: lookups.iter()
+                .map(|x| Ok(MessageAddressTableLookup { 
+                    account_key: Pubkey::from_str(&x.account_key)?,
+                    writable_indexes: x.writable_indexes.clone(),
+                    readonly_indexes: x.readonly_indexes.clone(),
+                }))
+                .collect::<Result<Vec<_>, LightNodeError>>()?,
+        }),
+    };
+    Ok(VersionedTransaction { signatures, message })
+}
+
+/// the ixs of the message which invoke the vote program, a tx which only references the vote program as an
+/// account isnt a vote (program ids are always static keys, never from a lookup table). the vote doesnt have
+/// to be the first ix (eg, after a compute budget ix) and a tx can carry more than one
+pub fn vote_instructions(msg: &VersionedMessage) -> Vec<&CompiledInstruction> { 
+    let vote_program_id = vote::program::id();
+    let static_keys = msg.static_account_keys();
+    msg.instructions().iter()
+        .filter(|ix| static_keys.get(ix.program_id_index as usize) == Some(&vote_program_id))
+        .collect()
+}
+
+/// the last voted slot and bank hash of any vote instruction which carries them
+/// (tower syncs are decoded into `CompactUpdateVoteState(Switch)` by `decode_vote_instruction`)
+pub fn voted_slot_and_hash(vote_ix: &VoteInstruction) -> Option<(u64, Hash)> { 
+    match vote_ix { 
+        VoteInstruction::Vote(v) 
+        | VoteInstruction::VoteSwitch(v, _) => v.last_voted_slot().map(|slot| (slot, v.hash)),
+        VoteInstruction::UpdateVoteState(v) 
+        | VoteInstruction::UpdateVoteStateSwitch(v, _) 
+        | VoteInstruction::CompactUpdateVoteState(v) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(v, _) => v.last_voted_slot().map(|slot| (slot, v.hash)),
+        _ => None,
+    }
+}
+
+/// (slot, confirmation count) of every slot in the vote's tower, a plain `Vote` doesnt carry lockouts so
+/// each of its slots gets a single confirmation
+pub fn vote_lockouts(vote_ix: &VoteInstruction) -> Vec<(u64, u32)> { 
+    match vote_ix { 
+        VoteInstruction::Vote(v) 
+        | VoteInstruction::VoteSwitch(v, _) => v.slots.iter().map(|slot| (*slot, 1)).collect(),
+        VoteInstruction::UpdateVoteState(v) 
+        | VoteInstruction::UpdateVoteStateSwitch(v, _) 
+        | VoteInstruction::CompactUpdateVoteState(v) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(v, _) => v.lockouts.iter()
+            .map(|lockout| (lockout.slot(), lockout.confirmation_count()))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// index (in the ix's accounts) of the authorized voter which has to sign the vote
+fn authorized_voter_index(vote_ix: &VoteInstruction) -> Option<usize> { 
+    match vote_ix { 
+        // vote account, slot hashes sysvar, clock sysvar, authorized voter
+        VoteInstruction::Vote(_) 
+        | VoteInstruction::VoteSwitch(_, _) => Some(3),
+        // vote account, authorized voter
+        VoteInstruction::UpdateVoteState(_) 
+        | VoteInstruction::UpdateVoteStateSwitch(_, _) 
+        | VoteInstruction::CompactUpdateVoteState(_) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(_, _) => Some(1),
+        _ => None,
+    }
+}
+
+pub fn switch_proof_hash(vote_ix: &VoteInstruction) -> Option<Hash> { 
+    match vote_ix { 
+        VoteInstruction::VoteSwitch(_, hash) 
+        | VoteInstruction::UpdateVoteStateSwitch(_, hash) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(_, hash) => Some(*hash),
+        _ => None,
+    }
+}
+
+pub fn vote_root(vote_ix: &VoteInstruction) -> Option<u64> { 
+    match vote_ix { 
+        VoteInstruction::UpdateVoteState(v) 
+        | VoteInstruction::UpdateVoteStateSwitch(v, _) 
+        | VoteInstruction::CompactUpdateVoteState(v) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(v, _) => v.root,
+        _ => None,
+    }
+}
+
+pub fn vote_timestamp(vote_ix: &VoteInstruction) -> Option<i64> { 
+    match vote_ix { 
+        VoteInstruction::Vote(v) 
+        | VoteInstruction::VoteSwitch(v, _) => v.timestamp,
+        VoteInstruction::UpdateVoteState(v) 
+        | VoteInstruction::UpdateVoteStateSwitch(v, _) 
+        | VoteInstruction::CompactUpdateVoteState(v) 
+        | VoteInstruction::CompactUpdateVoteStateSwitch(v, _) => v.timestamp,
+        _ => None,
+    }
+}
+
+/// (vote account, slot) of the votes which both sources saw for the same slot but with a different bank hash
+/// eg, to cross check the votes parsed out of blocks against the ones from `voteSubscribe`
+pub fn cross_check_votes(block_votes: &[ParsedVote], stream_votes: &[ParsedVote]) -> Vec<(Pubkey, u64)> { 
+    let block_hashes = block_votes.iter()
+        .map(|vote| ((vote.vote_account, vote.slot), vote.bank_hash))
+        .collect::<HashMap<_, _>>();
+    let mut mismatches = stream_votes.iter()
+        .filter(|vote| block_hashes.get(&(vote.vote_account, vote.slot)).map(|hash| *hash != vote.bank_hash).unwrap_or(false))
+        .map(|vote| (vote.vote_account, vote.slot))
+        .collect::<Vec<_>>();
+    mismatches.sort();
+    mismatches.dedup();
+    mismatches
+}
+
+/// sums the stake of every vote account which voted on `slot`, each vote account is counted once
+pub fn confirmed_stake_for_slot(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, slot: u64) -> u128 { 
+    let voters = votes.iter()
+        .filter(|vote| vote.counts() && vote.slot == slot)
+        .map(|vote| &vote.vote_account)
+        .collect::<HashSet<_>>();
+    voters.iter()
+        .map(|pubkey| stakes.get(*pubkey).map(|x| x.activated_stake as u128).unwrap_or(0))
+        .sum()
+}
+
+/// stake weighted median of the vote timestamps for `slot`, votes without a timestamp are left out
+/// (an estimate of the block time which doesnt rely on the rpc)
+pub fn stake_weighted_timestamp(votes: &[ParsedVote], slot: u64) -> Option<i64> { 
+    let mut voters = HashSet::new();
+    let mut timestamps = votes.iter()
+        .filter(|vote| vote.counts() && vote.slot == slot)
+        .filter_map(|vote| vote.timestamp.map(|timestamp| (vote, timestamp)))
+        .filter(|(vote, _)| voters.insert(vote.vote_account))
+        .map(|(vote, timestamp)| (timestamp, vote.stake))
+        .collect::<Vec<_>>();
+    timestamps.sort();
+    stake_weighted_percentile(&timestamps, 0.5)
+}
+
+// the first timestamp where `percentile` of the stake is at or below it, `timestamps` has to be sorted
+fn stake_weighted_percentile(timestamps: &[(i64, u64)], percentile: f64) -> Option<i64> { 
+    let total_stake = timestamps.iter().map(|(_, stake)| *stake as u128).sum::<u128>();
+    let mut stake = 0u128;
+    for (timestamp, vote_stake) in timestamps.iter() { 
+        stake += *vote_stake as u128;
+        if stake as f64 >= percentile * total_stake as f64 { 
+            return Some(*timestamp);
+        }
+    }
+    None
+}
+
+/// the cluster's clock from the vote timestamps, `low` and `high` are the stake weighted
+/// 25th and 75th percentiles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterTimeEstimate { 
+    pub slot: u64,
+    pub timestamp: i64,
+    pub low: i64,
+    pub high: i64,
+    // fraction of the total stake which voted with a timestamp
+    pub timestamp_stake_fraction: f64,
+}
+
+/// `ClusterTimeEstimate` from the latest timestamped vote of each vote account, None when no vote has a timestamp
+pub fn cluster_time_from_votes(votes: &[ParsedVote], slot: u64, total_stake: u128) -> Option<ClusterTimeEstimate> { 
+    let mut latest: HashMap<Pubkey, (u64, i64, u64)> = HashMap::new();
+    for vote in votes.iter().filter(|vote| vote.counts()) { 
+        let timestamp = match vote.timestamp { 
+            Some(timestamp) => timestamp,
+            None => continue,
+        };
+        let entry = latest.entry(vote.vote_account).or_insert((vote.slot, timestamp, vote.stake));
+        if vote.slot > entry.0 { 
+            *entry = (vote.slot, timestamp, vote.stake);
+        }
+    }
+    let mut timestamps = latest.values().map(|(_, timestamp, stake)| (*timestamp, *stake)).collect::<Vec<_>>();
+    timestamps.sort();
+
+    let timestamp_stake = timestamps.iter().map(|(_, stake)| *stake as u128).sum::<u128>();
+    Some(ClusterTimeEstimate { 
+        slot,
+        timestamp: stake_weighted_percentile(&timestamps, 0.5)?,
+        low: stake_weighted_percentile(&timestamps, 0.25)?,
+        high: stake_weighted_percentile(&timestamps, 0.75)?,
+        timestamp_stake_fraction: if total_stake == 0 { 0.0 } else { timestamp_stake as f64 / total_stake as f64 },
+    })
+}
+
+/// (vote account, seconds off) of the votes for `slot` whose timestamp is further than `max_drift` from
+/// the block time of the slot, eg, validators with a misconfigured clock. votes without a timestamp
+/// (or which dont count, eg, bad signatures) are skipped
+pub fn detect_clock_drift(votes: &[ParsedVote], slot: u64, block_time: i64, max_drift: Duration) -> Vec<(Pubkey, i64)> { 
+    let mut drifted = votes.iter()
+        .filter(|vote| vote.counts() && vote.slot == slot)
+        .filter_map(|vote| vote.timestamp.map(|timestamp| (vote.vote_account, timestamp - block_time)))
+        .filter(|(_, drift)| drift.unsigned_abs() > max_drift.as_secs())
+        .collect::<Vec<_>>();
+    drifted.sort();
+    drifted.dedup();
+    drifted
+}
+
+/// the stake which voted for a bank hash at a slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BankHashVotes { 
+    pub slot: u64,
+    pub bank_hash: Hash,
+    pub stake: u128,
+    // number of vote accounts
+    pub num_votes: usize,
+    pub stake_fraction: f64,
+    pub supermajority: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoteAggregate { 
+    pub total_stake: u128,
+    pub threshold: Threshold,
+    // by slot, then by stake (most first)
+    pub bank_hashes: Vec<BankHashVotes>,
+}
+
+/// groups the votes by (slot, bank hash) and sums the stake of each group, a vote account which voted
+/// more than once for a slot only counts with its last vote. groups with at least `threshold` of
+/// `total_stake` are a supermajority
+pub fn aggregate_votes(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, total_stake: u128, threshold: Threshold) -> VoteAggregate { 
+    let mut latest_votes = HashMap::new();
+    for vote in votes.iter().filter(|vote| vote.counts()) { 
+        latest_votes.insert((vote.vote_account, vote.slot), vote.bank_hash);
+    }
+
+    let mut groups: HashMap<(u64, Hash), (u128, usize)> = HashMap::new();
+    for ((vote_account, slot), bank_hash) in latest_votes { 
+        let stake = stakes.get(&vote_account).map(|x| x.activated_stake as u128).unwrap_or(0);
+        let group = groups.entry((slot, bank_hash)).or_default();
+        group.0 += stake;
+        group.1 += 1;
+    }
+
+    let mut bank_hashes = groups.into_iter()
+        .map(|((slot, bank_hash), (stake, num_votes))| { 
+            let stake_fraction = if total_stake == 0 { 0.0 } else { stake as f64 / total_stake as f64 };
+            BankHashVotes { 
+                slot,
+                bank_hash,
+                stake,
+                num_votes,
+                stake_fraction,
+                supermajority: total_stake > 0 && threshold.is_met(stake, total_stake),
+            }
+        })
+        .collect::<Vec<_>>();
+    bank_hashes.sort_by(|a, b| a.slot.cmp(&b.slot).then(b.stake.cmp(&a.stake)));
+
+    VoteAggregate { total_stake, threshold, bank_hashes }
+}
+
+/// `confirmed_stake_for_slot` but also crediting the votes for descendants of `slot`, ie,
+/// the votes which have `slot` in their tower
+pub fn tower_stake_for_slot(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, slot: u64) -> u128 { 
+    let voters = votes.iter()
+        .filter(|vote| vote.counts())
+        .filter(|vote| vote.slot == slot || vote.voted_slots.iter().any(|(voted_slot, _)| *voted_slot == slot))
+        .map(|vote| &vote.vote_account)
+        .collect::<HashSet<_>>();
+    voters.iter()
+        .map(|pubkey| stakes.get(*pubkey).map(|x| x.activated_stake as u128).unwrap_or(0))
+        .sum()
+}
+
+/// true when at least `threshold` of the total stake voted on `slot`
+pub fn is_optimistically_confirmed(votes: &[ParsedVote], stakes: &HashMap<Pubkey, StakeInfo>, slot: u64, threshold: Threshold) -> bool { 
+    let total_stake = total_stake(stakes);
+    let confirmed_stake = confirmed_stake_for_slot(votes, stakes, slot);
+    total_stake > 0 && threshold.is_met(confirmed_stake, total_stake)
+}
+
+/// sums the stake of every vote account which voted for `bank_hash`, each vote account is counted once
+pub fn stake_for_bank_hash(votes: &[ParsedVote], bank_hash: &Hash) -> u128 { 
+    let mut voters = HashSet::new();
+    votes.iter()
+        .filter(|vote| vote.counts() && vote.bank_hash == *bank_hash)
+        .filter(|vote| voters.insert(vote.vote_account))
+        .map(|vote| vote.stake as u128)
+        .sum()
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// estimates the time of `slot` from the vote timestamps in its block and the few blocks before it
+    pub async fn cluster_time_estimate(&self, slot: u64) -> Result<Option<ClusterTimeEstimate>, LightNodeError> { 
+        let first_slot = slot.saturating_sub(CLUSTER_TIME_SLOTS);
+        let votes = self.parse_block_votes(first_slot, slot - first_slot + 1).await?;
+        let total_stake = total_stake(&self.stakes_for_slot(slot).await?.stakes);
+        Ok(cluster_time_from_votes(&votes, slot, total_stake))
+    }
+
+    /// vote account -> its authorized voter at `epoch`, read from the vote accounts (and cached)
+    /// vote accounts which dont exist or dont have a voter for the epoch are left out
+    pub async fn authorized_voters(&self, vote_accounts: &[Pubkey], epoch: u64) -> Result<HashMap<Pubkey, Pubkey>, LightNodeError> { 
+        let mut missing = { 
+            let cache = self.authorized_voters.read().unwrap();
+            vote_accounts.iter().filter(|x| !cache.contains_key(&(**x, epoch))).copied().collect::<Vec<_>>()
+        };
+        missing.sort();
+        missing.dedup();
+
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) { 
+            let (_, accounts) = self.get_multiple_accounts(chunk).await?;
+            let mut cache = self.authorized_voters.write().unwrap();
+            // the voters of older epochs arent needed anymore
+            cache.retain(|(_, cached_epoch), _| *cached_epoch + 1 >= epoch);
+            for (vote_account, account) in chunk.iter().zip(accounts.iter()) { 
+                let voter = match account { 
+                    Some(account) => VoteState::deserialize(&account.data)
+                        .map_err(|e| LightNodeError::InvalidResponse(format!("invalid vote account {}: {:?}", vote_account, e)))?
+                        .authorized_voters()
+                        .get_authorized_voter(epoch),
+                    None => None,
+                };
+                cache.insert((*vote_account, epoch), voter);
+            }
+        }
+
+        let cache = self.authorized_voters.read().unwrap();
+        Ok(vote_accounts.iter()
+            .filter_map(|x| cache.get(&(*x, epoch)).copied().flatten().map(|voter| (*x, voter)))
+            .collect())
+    }
+
+    /// parses every vote tx in the blocks from `target_slot` to `target_slot + slots_ahead`
+    #[tracing::instrument(skip(self))]
+    pub async fn parse_block_votes(&self, target_slot: u64, slots_ahead: u64) -> Result<Vec<ParsedVote>, LightNodeError> {
+        // the votes are weighted with the stakes of the epoch theyre in
+        let leader_stakes = self.stakes_for_slot(target_slot).await?;
+        // the votes need the full txs but not the rewards
+        let options = GetBlockOptions::new(self.encoding).rewards(false);
+
+        let mut votes = vec![];
+        // the authorized voter which signed each vote (if any), checked once all the votes are parsed
+        let mut voters = vec![];
+        let mut num_non_vote_txs = 0;
+
+        for i in 0..slots_ahead {
+            let slot = target_slot + i;
+
+            let block = match self.wait_for_block_with_options(slot, &options).await { 
+                Ok(block) => block,
+                // no block was produced so there are no votes
+                Err(LightNodeError::SlotSkipped(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let transactions = match block.transactions { 
+                Some(transactions) => transactions,
+                None => continue,
+            };
+    
+            let mut num_failed_votes = 0;
+            // txs / vote ixs which cant be decoded and votes whose lookup tables cant be loaded (eg, a closed table),
+            // theyre skipped instead of failing the whole block
+            let mut num_undecodable = 0;
+            let mut num_unresolved = 0;
+            // the signatures of a block's vote txs are checked together once theyre all parsed
+            let first_vote = votes.len();
+            let mut vote_txs = vec![];
+            let mut tx_of_vote = vec![];
+            for tx_with_meta in transactions.iter() {
+                let tx = match decode_transaction(&tx_with_meta.transaction) { 
+                    Ok(tx) => tx,
+                    Err(e) => { 
+                        tracing::debug!("skipping a tx in slot {} which cant be decoded: {}", slot, e);
+                        num_undecodable += 1;
+                        continue;
+                    }
+                };
+    
+                let msg = &tx.message;
+                let vote_ixs = vote_instructions(msg);
+                if vote_ixs.is_empty() { 
+                    num_non_vote_txs += 1;
+                    continue;
+                }
+                // a vote which landed but failed (eg, too old) didnt change the vote account at all
+                if tx_with_meta.meta.as_ref().and_then(|meta| meta.err.as_ref()).is_some() { 
+                    num_failed_votes += 1;
+                    continue;
+                }
+                // the vote account itself can be loaded through an address lookup table
+                let account_keys = match self.resolve_account_keys(msg).await { 
+                    Ok(account_keys) => account_keys,
+                    Err(e) => { 
+                        tracing::debug!("skipping a vote tx in slot {} whose accounts cant be resolved: {}", slot, e);
+                        num_unresolved += 1;
+                        continue;
+                    }
+                };
+
+                for ix in vote_ixs { 
+                    let vote_ix = match decode_vote_instruction(&ix.data) { 
+                        Some(vote_ix) => vote_ix,
+                        None => { 
+                            num_undecodable += 1;
+                            continue;
+                        }
+                    };
+                    let (voted_slot, bank_hash) = match voted_slot_and_hash(&vote_ix) { 
+                        Some(x) => x,
+                        None => continue,
+                    };
+
+                    // the vote account is the first account of the vote ix
+                    let vote_account = match ix.accounts.get(0).and_then(|i| account_keys.get(*i as usize)) { 
+                        Some(pubkey) => *pubkey,
+                        None => continue,
+                    };
+
+                    let voter = authorized_voter_index(&vote_ix)
+                        .and_then(|i| ix.accounts.get(i))
+                        .filter(|i| msg.is_signer(**i as usize))
+                        .and_then(|i| account_keys.get(*i as usize))
+                        .copied();
+                    voters.push(voter);
+                    tx_of_vote.push(vote_txs.len());
+
+                    // the fee payer isnt necessarily the node identity so its resolved from the vote account
+                    let stake_info = leader_stakes.stakes.get(&vote_account);
+                    tracing::debug!(block_slot = slot, %vote_account, voted_slot, %bank_hash, "parsed vote");
+
+                    let timestamp = vote_timestamp(&vote_ix);
+                    let switch_proof_hash = switch_proof_hash(&vote_ix);
+                    let voted_slots = vote_lockouts(&vote_ix);
+                    let root = vote_root(&vote_ix);
+                    votes.push(ParsedVote { 
+                        node_pubkey: stake_info.map(|x| x.node_pubkey),
+                        vote_account,
+                        slot: voted_slot,
+                        bank_hash,
+                        stake: stake_info.map(|x| x.activated_stake).unwrap_or(0),
+                        signature_verified: false,
+                        voter_authorized: false,
+                        timestamp,
+                        switch_proof_hash,
+                        voted_slots,
+                        root,
+                        slot_hash_mismatch: false,
+                        signed_vote: Some(Arc::new(SignedVote { 
+                            signatures: tx.signatures.clone(),
+                            message: msg.serialize(),
+                            vote_instruction: vote_ix,
+                        })),
+                        source: VoteSource::Block,
+                    });
+                }
+                vote_txs.push(tx);
+            }
+
+            let verified = verify_transactions_batch(&vote_txs);
+            for (vote, i) in votes[first_vote..].iter_mut().zip(tx_of_vote) { 
+                vote.signature_verified = verified[i].is_ok();
+                if !vote.signature_verified { 
+                    self.metrics.invalid_vote_signatures.inc();
+                }
+            }
+            self.metrics.votes_parsed.inc_by((votes.len() - first_vote) as u64);
+            self.metrics.failed_votes.inc_by(num_failed_votes);
+            if num_failed_votes > 0 { 
+                tracing::debug!("skipped {} failed votes in slot {}", num_failed_votes, slot);
+            }
+            if num_undecodable + num_unresolved > 0 { 
+                tracing::warn!("skipped {} undecodable txs / vote ixs and {} votes with unresolved accounts in slot {}", num_undecodable, num_unresolved, slot);
+            }
+        }
+
+        let vote_accounts = votes.iter().map(|vote| vote.vote_account).collect::<Vec<_>>();
+        let authorized_voters = self.authorized_voters(&vote_accounts, leader_stakes.epoch).await?;
+        for (vote, voter) in votes.iter_mut().zip(voters) { 
+            vote.voter_authorized = voter.is_some() && authorized_voters.get(&vote.vote_account) == voter.as_ref();
+        }
+        let equivocations = self.vote_tracker.lock().unwrap().insert_all(&votes);
+        for report in equivocations { 
+            tracing::warn!("vote account {} voted for both {} and {} at slot {}", report.vote_account, report.first_bank_hash, report.second_bank_hash, report.slot);
+            self.equivocations.lock().unwrap().push(report);
+        }
+        // after the equivocations, a vote for a hash which isnt on our fork is still evidence
+        self.check_slot_hashes(&mut votes).await?;
+        { 
+            let mut root_tracker = self.root_tracker.lock().unwrap();
+            // the roots are weighted with the stakes of a single epoch
+            if root_tracker.as_ref().map(|(epoch, _)| *epoch) != Some(leader_stakes.epoch) { 
+                *root_tracker = Some((leader_stakes.epoch, RootTracker::new(total_stake(&leader_stakes.stakes))));
+            }
+            if let Some((_, tracker)) = root_tracker.as_mut() { 
+                tracker.insert_all(&votes);
+            }
+        }
+        tracing::debug!("skipped {} non-vote txs in slots {}..{}", num_non_vote_txs, target_slot, target_slot + slots_ahead);
+
+        Ok(votes)
+    }
+}
+
+pub async fn parse_block_votes(target_slot: u64, slots_ahead: u64, endpoint: &str) -> Result<Vec<ParsedVote>, LightNodeError> {
+    LightClient::new(endpoint).parse_block_votes(target_slot, slots_ahead).await
+}
+
+#[cfg(test)]
+mod tests { 
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::AccountMeta, system_instruction};
+    use solana_sdk::vote::state::{Lockout, Vote, VoteStateUpdate};
+
+    use super::*;
+    use crate::tower_sync::tests::tower_sync_data;
+
+    const TIMESTAMP: i64 = 1_700_000_000;
+
+    fn bank_hash() -> Hash { 
+        Hash::new(&[0x11; 32])
+    }
+
+    fn switch_hash() -> Hash { 
+        Hash::new(&[0x33; 32])
+    }
+
+    // the same tower as `tower_sync_data`
+    fn vote_state_update() -> VoteStateUpdate { 
+        VoteStateUpdate { 
+            lockouts: [(1200, 4), (1202, 3), (1203, 2), (1210, 1)].into_iter()
+                .map(|(slot, confirmation_count)| Lockout::new_with_confirmation_count(slot, confirmation_count))
+                .collect(),
+            root: Some(1000),
+            hash: bank_hash(),
+            timestamp: Some(TIMESTAMP),
+        }
+    }
+
+    // (variant, ix data) of every vote ix, serialized the way the vote program reads them
+    fn serialized_vote_ixs() -> Vec<(&'static str, Vec<u8>)> { 
+        let mut vote = Vote::new(vec![1207, 1210], bank_hash());
+        vote.timestamp = Some(TIMESTAMP);
+        let serialize = |vote_ix: VoteInstruction| bincode::serialize(&vote_ix).unwrap();
+        vec![
+            ("Vote", serialize(VoteInstruction::Vote(vote.clone()))),
+            ("VoteSwitch", serialize(VoteInstruction::VoteSwitch(vote, switch_hash()))),
+            ("UpdateVoteState", serialize(VoteInstruction::UpdateVoteState(vote_state_update()))),
+            ("UpdateVoteStateSwitch", serialize(VoteInstruction::UpdateVoteStateSwitch(vote_state_update(), switch_hash()))),
+            ("CompactUpdateVoteState", serialize(VoteInstruction::CompactUpdateVoteState(vote_state_update()))),
+            ("CompactUpdateVoteStateSwitch", serialize(VoteInstruction::CompactUpdateVoteStateSwitch(vote_state_update(), switch_hash()))),
+            ("TowerSync", tower_sync_data(None)),
+            ("TowerSyncSwitch", tower_sync_data(Some(switch_hash()))),
+        ]
+    }
+
+    #[test]
+    fn test_vote_fields_of_every_variant() { 
+        for (variant, data) in serialized_vote_ixs() { 
+            let vote_ix = decode_vote_instruction(&data).unwrap_or_else(|| panic!("{} didnt decode", variant));
+            let is_vote = variant.starts_with("Vote");
+
+            assert_eq!(voted_slot_and_hash(&vote_ix), Some((1210, bank_hash())), "{}", variant);
+            assert_eq!(vote_timestamp(&vote_ix), Some(TIMESTAMP), "{}", variant);
+            let expected_switch = variant.ends_with("Switch").then(switch_hash);
+            assert_eq!(switch_proof_hash(&vote_ix), expected_switch, "{}", variant);
+            if is_vote { 
+                assert_eq!(vote_lockouts(&vote_ix), vec![(1207, 1), (1210, 1)], "{}", variant);
+                assert_eq!(vote_root(&vote_ix), None, "{}", variant);
+                assert_eq!(authorized_voter_index(&vote_ix), Some(3), "{}", variant);
+            } else { 
+                assert_eq!(vote_lockouts(&vote_ix), vec![(1200, 4), (1202, 3), (1203, 2), (1210, 1)], "{}", variant);
+                assert_eq!(vote_root(&vote_ix), Some(1000), "{}", variant);
+                assert_eq!(authorized_voter_index(&vote_ix), Some(1), "{}", variant);
+            }
+        }
+    }
+
+    #[test]
+    fn test_voted_slot_and_hash_tower_sync() { 
+        // what validators send now, it has to come out with its bank hash like the older ixs
+        for switch in [None, Some(switch_hash())] { 
+            let vote_ix = decode_vote_instruction(&tower_sync_data(switch)).unwrap();
+            assert_eq!(voted_slot_and_hash(&vote_ix), Some((1210, bank_hash())));
+        }
+
+        // an empty tower has no voted slot
+        let mut data = crate::tower_sync::TOWER_SYNC_TAG.to_le_bytes().to_vec();
+        data.extend(1000u64.to_le_bytes());
+        data.push(0);
+        data.extend(bank_hash().to_bytes());
+        data.push(0);
+        data.extend([0x22; 32]);
+        let vote_ix = decode_vote_instruction(&data).unwrap();
+        assert_eq!(voted_slot_and_hash(&vote_ix), None);
+    }
+
+    #[test]
+    fn test_undecodable_vote_ix() { 
+        // eg, `Withdraw` carries no vote and garbage doesnt decode at all
+        let withdraw = bincode::serialize(&VoteInstruction::Withdraw(42)).unwrap();
+        assert_eq!(decode_vote_instruction(&withdraw).and_then(|vote_ix| voted_slot_and_hash(&vote_ix)), None);
+        assert_eq!(decode_vote_instruction(&[0xff, 0xff, 0xff, 0xff, 1, 2, 3]), None);
+    }
+
+    fn vote_ix(vote_account: &Pubkey, authorized_voter: &Pubkey) -> solana_sdk::instruction::Instruction { 
+        vote::instruction::vote(vote_account, authorized_voter, Vote::new(vec![1210], bank_hash()))
+    }
+
+    #[test]
+    fn test_vote_after_compute_budget_ix() { 
+        let (node, vote_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ixs = [ComputeBudgetInstruction::set_compute_unit_limit(2_100), vote_ix(&vote_account, &node)];
+        let msg = VersionedMessage::Legacy(legacy::Message::new(&ixs, Some(&node)));
+
+        let vote_ixs = vote_instructions(&msg);
+        assert_eq!(vote_ixs.len(), 1);
+        assert_eq!(vote_ixs[0], &msg.instructions()[1]);
+        let vote_ix = decode_vote_instruction(&vote_ixs[0].data).unwrap();
+        assert_eq!(voted_slot_and_hash(&vote_ix), Some((1210, bank_hash())));
+        // the vote account is the first account of the vote ix, not of the tx
+        assert_eq!(msg.static_account_keys()[vote_ixs[0].accounts[0] as usize], vote_account);
+    }
+
+    #[test]
+    fn test_vote_program_only_as_account() { 
+        let payer = Pubkey::new_unique();
+        let mut transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000);
+        transfer.accounts.push(AccountMeta::new_readonly(vote::program::id(), false));
+        let msg = VersionedMessage::Legacy(legacy::Message::new(&[transfer], Some(&payer)));
+
+        assert!(msg.static_account_keys().contains(&vote::program::id()));
+        assert!(vote_instructions(&msg).is_empty());
+    }
+
+    fn signed(message: VersionedMessage) -> VersionedTransaction { 
+        let signatures = vec![Signature::try_from(&[7u8; 64][..]).unwrap(); message.header().num_required_signatures as usize];
+        VersionedTransaction { signatures, message }
+    }
+
+    fn legacy_vote_tx() -> VersionedTransaction { 
+        let (node, vote_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut msg = legacy::Message::new(&[vote_ix(&vote_account, &node)], Some(&node));
+        msg.recent_blockhash = bank_hash();
+        signed(VersionedMessage::Legacy(msg))
+    }
+
+    fn v0_vote_tx() -> VersionedTransaction { 
+        let legacy = legacy_vote_tx();
+        let mut msg = v0::Message { 
+            header: *legacy.message.header(),
+            account_keys: legacy.message.static_account_keys().to_vec(),
+            recent_blockhash: bank_hash(),
+            instructions: legacy.message.instructions().to_vec(),
+            address_table_lookups: vec![MessageAddressTableLookup { 
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1, 2],
+            }],
+        };
+        msg.instructions[0].accounts.push(msg.account_keys.len() as u8);
+        signed(VersionedMessage::V0(msg))
+    }
+
+    // the tx as the rpc returns it with the `json` encoding
+    fn json_encoded(tx: &VersionedTransaction) -> EncodedTransaction { 
+        let msg = &tx.message;
+        let mut message = serde_json::json!({ 
+            "header": msg.header(),
+            "accountKeys": msg.static_account_keys().iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            "recentBlockhash": msg.recent_blockhash().to_string(),
+            "instructions": msg.instructions().iter()
+                .map(|ix| serde_json::json!({ 
+                    "programIdIndex": ix.program_id_index,
+                    "accounts": ix.accounts,
+                    "data": bs58::encode(&ix.data).into_string(),
+                }))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(lookups) = msg.address_table_lookups() { 
+            message["addressTableLookups"] = lookups.iter()
+                .map(|x| serde_json::json!({ 
+                    "accountKey": x.account_key.to_string(),
+                    "writableIndexes": x.writable_indexes,
+                    "readonlyIndexes": x.readonly_indexes,
+                }))
+                .collect();
+        }
+        let signatures = tx.signatures.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({ "signatures": signatures, "message": message })).unwrap()
+    }
+
+    fn parsed_account_keys(tx: &VersionedTransaction) -> serde_json::Value { 
+        tx.message.static_account_keys().iter()
+            .map(|x| serde_json::json!({ "pubkey": x.to_string(), "writable": true, "signer": false, "source": "transaction" }))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_json_transaction() { 
+        for tx in [legacy_vote_tx(), v0_vote_tx()] { 
+            let encoded = json_encoded(&tx);
+            assert!(matches!(encoded, EncodedTransaction::Json(_)));
+            assert_eq!(decode_transaction(&encoded).unwrap(), tx);
+        }
+        // a legacy message stays legacy and a v0 one keeps its lookups
+        assert!(matches!(decode_transaction(&json_encoded(&legacy_vote_tx())).unwrap().message, VersionedMessage::Legacy(_)));
+        let v0_tx = v0_vote_tx();
+        assert_eq!(decode_transaction(&json_encoded(&v0_tx)).unwrap().message.address_table_lookups(), v0_tx.message.address_table_lookups());
+    }
+
+    #[test]
+    fn test_decode_json_parsed_transaction() { 
+        let tx = legacy_vote_tx();
+        let encoded: EncodedTransaction = serde_json::from_value(serde_json::json!({ 
+            "signatures": [tx.signatures[0].to_string()],
+            "message": { 
+                "accountKeys": parsed_account_keys(&tx),
+                "recentBlockhash": bank_hash().to_string(),
+                "instructions": [],
+            },
+        })).unwrap();
+        assert!(matches!(&encoded, EncodedTransaction::Json(UiTransaction { message: UiMessage::Parsed(_), .. })));
+        match decode_transaction(&encoded) { 
+            Err(LightNodeError::UnsupportedEncoding(encoding)) => assert!(encoding.starts_with("jsonParsed")),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_decode_accounts_transaction() { 
+        let tx = legacy_vote_tx();
+        let encoded: EncodedTransaction = serde_json::from_value(serde_json::json!({ 
+            "signatures": [tx.signatures[0].to_string()],
+            "accountKeys": parsed_account_keys(&tx),
+        })).unwrap();
+        assert!(matches!(encoded, EncodedTransaction::Accounts(_)));
+        match decode_transaction(&encoded) { 
+            Err(LightNodeError::UnsupportedEncoding(encoding)) => assert!(encoding.starts_with("accounts")),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_decode_binary_transaction() { 
+        for tx in [legacy_vote_tx(), v0_vote_tx()] { 
+            let bytes = bincode::serialize(&tx).unwrap();
+            let base58 = bs58::encode(&bytes).into_string();
+            let encodings = [
+                EncodedTransaction::Binary(base58.clone(), TransactionBinaryEncoding::Base58),
+                EncodedTransaction::Binary(BASE64.encode(&bytes), TransactionBinaryEncoding::Base64),
+                EncodedTransaction::LegacyBinary(base58),
+            ];
+            for encoded in encodings.iter() { 
+                assert_eq!(decode_transaction(encoded).unwrap(), tx, "{:?}", encoded);
+            }
+        }
+
+        // base64 where base58 is expected
+        let base64 = BASE64.encode(bincode::serialize(&legacy_vote_tx()).unwrap());
+        assert!(matches!(
+            decode_transaction(&EncodedTransaction::Binary(base64.clone(), TransactionBinaryEncoding::Base58)),
+            Err(LightNodeError::Base58(_))
+        ));
+        // and a truncated tx
+        let truncated = BASE64.encode(&BASE64.decode(&base64).unwrap()[..40]);
+        assert!(matches!(
+            decode_transaction(&EncodedTransaction::Binary(truncated, TransactionBinaryEncoding::Base64)),
+            Err(LightNodeError::Bincode(_))
+        ));
+    }
+}
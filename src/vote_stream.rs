@@ -0,0 +1,113 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_response::RpcVote};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::stake::EpochStakes;
+use crate::transport::RpcTransport;
+use crate::vote::{ParsedVote, VoteSource};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+// votes buffered while the reader is busy, the subscription waits when its full
+const VOTE_BUFFER: usize = 4096;
+
+/// subscribes to `voteSubscribe`, reconnecting (and resubscribing) whenever the connection drops
+/// (the node has to run with `--rpc-pubsub-enable-vote-subscription`)
+async fn subscribe_votes(ws_endpoint: String, votes: mpsc::Sender<RpcVote>) { 
+    loop { 
+        match PubsubClient::new(&ws_endpoint).await { 
+            Ok(client) => match client.vote_subscribe().await { 
+                Ok((mut notifications, unsubscribe)) => { 
+                    while let Some(vote) = notifications.next().await { 
+                        if votes.send(vote).await.is_err() { 
+                            unsubscribe().await;
+                            return;
+                        }
+                    }
+                    unsubscribe().await;
+                }
+                Err(e) => tracing::debug!("failed to subscribe to votes: {}", e),
+            },
+            Err(e) => tracing::debug!("failed to connect to {}: {}", ws_endpoint, e),
+        }
+
+        tracing::debug!("vote subscription closed, reconnecting in {:?}", RECONNECT_DELAY);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// votes streamed from `voteSubscribe`, parsed into the same `ParsedVote`s as `parse_block_votes`
+/// (so theyre aggregated the same way, eg, with `confirmed_stake_for_slot`)
+pub struct VoteStream { 
+    votes: mpsc::Receiver<RpcVote>,
+    leader_stakes: Arc<EpochStakes>,
+    subscription: JoinHandle<()>,
+}
+
+impl VoteStream { 
+    /// the next vote, None once the subscription is gone
+    pub async fn next(&mut self) -> Option<Result<ParsedVote, LightNodeError>> { 
+        loop { 
+            let vote = self.votes.recv().await?;
+            match self.parse_vote(&vote) { 
+                Ok(Some(vote)) => return Some(Ok(vote)),
+                // votes without a slot dont carry anything to aggregate
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    fn parse_vote(&self, vote: &RpcVote) -> Result<Option<ParsedVote>, LightNodeError> { 
+        let slot = match vote.slots.last() { 
+            Some(slot) => *slot,
+            None => return Ok(None),
+        };
+        let vote_account = Pubkey::from_str(&vote.vote_pubkey)?;
+        let bank_hash = Hash::from_str(&vote.hash)
+            .map_err(|_| LightNodeError::InvalidResponse(format!("invalid vote hash {}", vote.hash)))?;
+        // unknown vote accounts count as zero stake
+        let stake_info = self.leader_stakes.stakes.get(&vote_account);
+
+        Ok(Some(ParsedVote { 
+            node_pubkey: stake_info.map(|x| x.node_pubkey),
+            vote_account,
+            slot,
+            bank_hash,
+            stake: stake_info.map(|x| x.activated_stake).unwrap_or(0),
+            signature_verified: false,
+            voter_authorized: false,
+            timestamp: vote.timestamp,
+            // not part of the notification
+            switch_proof_hash: None,
+            // the notification only has the slots
+            voted_slots: vote.slots.iter().map(|slot| (*slot, 1)).collect(),
+            root: None,
+            // the subscription votes arent checked against the slot hashes
+            slot_hash_mismatch: false,
+            signed_vote: None,
+            source: VoteSource::Subscription,
+        }))
+    }
+}
+
+impl Drop for VoteStream { 
+    fn drop(&mut self) { 
+        self.subscription.abort();
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    /// starts streaming votes over the client's websocket endpoint
+    pub async fn vote_stream(&self) -> Result<VoteStream, LightNodeError> { 
+        let leader_stakes = self.leader_stakes().await?;
+        let (sender, votes) = mpsc::channel(VOTE_BUFFER);
+        let subscription = tokio::spawn(subscribe_votes(self.ws_endpoint.clone(), sender));
+
+        Ok(VoteStream { votes, leader_stakes, subscription })
+    }
+}
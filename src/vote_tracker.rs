@@ -0,0 +1,272 @@
+use std::{collections::{BTreeMap, HashMap}, fs, io::ErrorKind, path::Path, sync::Arc};
+
+use serde::{Serialize, Deserialize};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use crate::error::LightNodeError;
+use crate::stake::Threshold;
+use crate::state::write_file;
+use crate::vote::{ParsedVote, SignedVote};
+
+// slots kept behind the highest slot seen, older votes are dropped
+// (every vote keeps its signed tx, so a slot is ~1500 txs)
+pub const DEFAULT_VOTE_WINDOW: u64 = 256;
+
+// the votes a `VoteTracker` has seen, saved next to the watcher's state.json
+pub const VOTES_FILE: &str = "votes.json";
+
+/// a vote account which voted for two different bank hashes at the same slot, with both signed
+/// votes when they came from blocks (slashing evidence which can be checked by anyone)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquivocationReport { 
+    pub vote_account: Pubkey,
+    pub node_pubkey: Option<Pubkey>,
+    pub slot: u64,
+    pub first_bank_hash: Hash,
+    pub second_bank_hash: Hash,
+    pub first_vote: Option<Arc<SignedVote>>,
+    pub second_vote: Option<Arc<SignedVote>>,
+}
+
+/// remembers the bank hash each vote account voted for at each slot (within a window of recent slots)
+/// to catch validators voting on two forks at the same slot
+pub struct VoteTracker { 
+    window: u64,
+    // slot -> vote account -> bank hash (and the signed vote)
+    votes: BTreeMap<u64, HashMap<Pubkey, (Hash, Option<Arc<SignedVote>>)>>,
+}
+
+// a vote in the votes file
+#[derive(Debug, Serialize, Deserialize)]
+struct SeenVote { 
+    slot: u64,
+    vote_account: Pubkey,
+    bank_hash: Hash,
+    signed_vote: Option<SignedVote>,
+}
+
+impl VoteTracker { 
+    pub fn new(window: u64) -> Self { 
+        VoteTracker { window, votes: BTreeMap::new() }
+    }
+
+    /// adds the votes saved in `dir`, so an equivocation straddling a restart is still caught
+    /// (nothing is added when nothing was saved yet)
+    pub fn load(&mut self, dir: &Path) -> Result<(), LightNodeError> { 
+        let seen: Vec<SeenVote> = match fs::read(dir.join(VOTES_FILE)) { 
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        for vote in seen { 
+            self.votes.entry(vote.slot)
+                .or_default()
+                .entry(vote.vote_account)
+                .or_insert((vote.bank_hash, vote.signed_vote.map(Arc::new)));
+        }
+        // the window may have shrunk since the votes were saved
+        if let Some(highest) = self.votes.keys().next_back().copied() { 
+            self.votes = self.votes.split_off(&highest.saturating_sub(self.window));
+        }
+        Ok(())
+    }
+
+    /// writes the votes in the window to `dir`
+    pub fn save(&self, dir: &Path) -> Result<(), LightNodeError> { 
+        let seen = self.votes.iter()
+            .flat_map(|(slot, votes)| votes.iter().map(move |(vote_account, (bank_hash, signed_vote))| SeenVote { 
+                slot: *slot,
+                vote_account: *vote_account,
+                bank_hash: *bank_hash,
+                signed_vote: signed_vote.as_deref().cloned(),
+            }))
+            .collect::<Vec<_>>();
+        write_file(dir, VOTES_FILE, &serde_json::to_vec(&seen)?)
+    }
+
+    /// records the vote, returns the equivocation when the vote account already voted for a different
+    /// bank hash at the same slot. votes which dont count (eg, bad signatures) arent attributed to anyone
+    pub fn insert(&mut self, vote: &ParsedVote) -> Option<EquivocationReport> { 
+        if !vote.counts() { 
+            return None;
+        }
+        let highest = self.votes.keys().next_back().copied().unwrap_or(vote.slot).max(vote.slot);
+        let oldest = highest.saturating_sub(self.window);
+        if vote.slot < oldest { 
+            return None;
+        }
+        // everything before the window is dropped
+        self.votes = self.votes.split_off(&oldest);
+
+        let (bank_hash, signed_vote) = self.votes.entry(vote.slot)
+            .or_default()
+            .entry(vote.vote_account)
+            .or_insert((vote.bank_hash, vote.signed_vote.clone()));
+        if *bank_hash == vote.bank_hash { 
+            return None;
+        }
+        Some(EquivocationReport { 
+            vote_account: vote.vote_account,
+            node_pubkey: vote.node_pubkey,
+            slot: vote.slot,
+            first_bank_hash: *bank_hash,
+            second_bank_hash: vote.bank_hash,
+            first_vote: signed_vote.clone(),
+            second_vote: vote.signed_vote.clone(),
+        })
+    }
+
+    pub fn insert_all(&mut self, votes: &[ParsedVote]) -> Vec<EquivocationReport> { 
+        votes.iter().filter_map(|vote| self.insert(vote)).collect()
+    }
+}
+
+impl Default for VoteTracker { 
+    fn default() -> Self { 
+        VoteTracker::new(DEFAULT_VOTE_WINDOW)
+    }
+}
+
+/// the highest slot which 2/3 of the stake has rooted, from the roots in the voters' towers
+/// (a finality signal which doesnt rely on the rpc's finalized commitment)
+pub struct RootTracker { 
+    total_stake: u128,
+    // vote account -> (highest root, stake)
+    roots: HashMap<Pubkey, (u64, u64)>,
+    root: u64,
+}
+
+impl RootTracker { 
+    pub fn new(total_stake: u128) -> Self { 
+        RootTracker { total_stake, roots: HashMap::new(), root: 0 }
+    }
+
+    pub fn insert(&mut self, vote: &ParsedVote) { 
+        let root = match vote.root { 
+            Some(root) if vote.counts() => root,
+            _ => return,
+        };
+        let entry = self.roots.entry(vote.vote_account).or_insert((root, vote.stake));
+        // a tower's root never goes back, an older vote landing late doesnt change it
+        if root < entry.0 { 
+            return;
+        }
+        *entry = (root, vote.stake);
+        self.update_root();
+    }
+
+    pub fn insert_all(&mut self, votes: &[ParsedVote]) { 
+        for vote in votes.iter() { 
+            self.insert(vote);
+        }
+    }
+
+    // the highest root where the stake of the voters rooted at or past it is a supermajority
+    fn update_root(&mut self) { 
+        let mut roots = self.roots.values().copied().collect::<Vec<_>>();
+        roots.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let mut stake = 0u128;
+        for (root, vote_stake) in roots { 
+            stake += vote_stake as u128;
+            if self.total_stake > 0 && Threshold::SUPERMAJORITY.is_met(stake, self.total_stake) { 
+                // roots only move forward
+                self.root = self.root.max(root);
+                return;
+            }
+        }
+    }
+
+    pub fn current_root(&self) -> u64 { 
+        self.root
+    }
+
+    /// stake of the voters whose root is at or past the current root
+    pub fn rooted_stake(&self) -> u128 { 
+        self.roots.values()
+            .filter(|(root, _)| *root >= self.root)
+            .map(|(_, stake)| *stake as u128)
+            .sum()
+    }
+
+    pub fn total_stake(&self) -> u128 { 
+        self.total_stake
+    }
+}
+
+#[cfg(test)]
+mod tests { 
+    use super::*;
+    use crate::vote::VoteSource;
+
+    fn vote(vote_account: Pubkey, slot: u64, bank_hash: Hash) -> ParsedVote { 
+        ParsedVote { 
+            node_pubkey: None,
+            vote_account,
+            slot,
+            bank_hash,
+            stake: 100,
+            signature_verified: false,
+            voter_authorized: false,
+            timestamp: None,
+            switch_proof_hash: None,
+            voted_slots: vec![(slot, 1)],
+            root: None,
+            slot_hash_mismatch: false,
+            signed_vote: None,
+            source: VoteSource::Subscription,
+        }
+    }
+
+    #[test]
+    fn test_conflicting_votes() { 
+        let mut tracker = VoteTracker::new(DEFAULT_VOTE_WINDOW);
+        let (vote_account, first, second) = (Pubkey::new_unique(), Hash::new_unique(), Hash::new_unique());
+        assert_eq!(tracker.insert(&vote(vote_account, 100, first)), None);
+        // the same vote again, and another validator on the other fork, are fine
+        assert_eq!(tracker.insert(&vote(vote_account, 100, first)), None);
+        assert_eq!(tracker.insert(&vote(Pubkey::new_unique(), 100, second)), None);
+
+        let report = tracker.insert(&vote(vote_account, 100, second)).unwrap();
+        assert_eq!((report.vote_account, report.slot), (vote_account, 100));
+        assert_eq!((report.first_bank_hash, report.second_bank_hash), (first, second));
+
+        // a block vote which doesnt count isnt attributed to the vote account
+        let mut unverified = vote(vote_account, 100, Hash::new_unique());
+        unverified.source = VoteSource::Block;
+        assert_eq!(tracker.insert(&unverified), None);
+    }
+
+    #[test]
+    fn test_window_eviction() { 
+        let mut tracker = VoteTracker::new(10);
+        let (vote_account, first, second) = (Pubkey::new_unique(), Hash::new_unique(), Hash::new_unique());
+        tracker.insert(&vote(vote_account, 100, first));
+        // slot 100 is the oldest slot of the window at 110 so its still kept
+        tracker.insert(&vote(Pubkey::new_unique(), 110, Hash::new_unique()));
+        assert!(tracker.insert(&vote(vote_account, 100, second)).is_some());
+
+        // and is dropped once the window moves past it
+        tracker.insert(&vote(Pubkey::new_unique(), 111, Hash::new_unique()));
+        assert!(!tracker.votes.contains_key(&100));
+        assert_eq!(tracker.insert(&vote(vote_account, 100, second)), None);
+    }
+
+    #[test]
+    fn test_save_and_load() { 
+        let dir = std::env::temp_dir().join(format!("lightnode-votes-{}", std::process::id()));
+        let (vote_account, first, second) = (Pubkey::new_unique(), Hash::new_unique(), Hash::new_unique());
+        let mut tracker = VoteTracker::new(10);
+        // nothing saved yet
+        tracker.load(&dir).unwrap();
+        tracker.insert(&vote(vote_account, 100, first));
+        tracker.save(&dir).unwrap();
+
+        // the equivocation is caught across the restart
+        let mut tracker = VoteTracker::new(10);
+        tracker.load(&dir).unwrap();
+        let report = tracker.insert(&vote(vote_account, 100, second)).unwrap();
+        assert_eq!((report.first_bank_hash, report.second_bank_hash), (first, second));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
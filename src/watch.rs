@@ -0,0 +1,445 @@
+use std::{collections::VecDeque, ops::RangeInclusive, path::Path, sync::Mutex, time::{Duration, SystemTime}};
+
+use futures::{Stream, StreamExt, future};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
+    rpc_response::SlotUpdate,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::TransactionDetails;
+use tokio::{sync::{mpsc, watch}, task::JoinHandle};
+
+use crate::client::LightClient;
+use crate::error::LightNodeError;
+use crate::state::WatchState;
+use crate::transport::RpcTransport;
+use crate::verify::VerificationResult;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationKind { 
+    // the confirmation threshold of the stake voted on the slot's verified bank hash
+    OptimisticallyConfirmed,
+    // a supermajority of the stake has the slot (or a later one) as its tower root
+    Rooted,
+}
+
+/// passed to the hooks of `LightClient::with_confirmation_hook` as the watcher confirms slots
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmationEvent { 
+    pub kind: ConfirmationKind,
+    pub slot: u64,
+    pub confirmed_stake: u128,
+    pub total_stake: u128,
+    // when the light node saw the slot confirmed
+    pub timestamp: SystemTime,
+}
+
+pub type ConfirmationHook = Box<dyn Fn(&ConfirmationEvent) + Send + Sync>;
+
+/// the registered hooks and the last root they were told about
+#[derive(Default)]
+pub struct ConfirmationHooks { 
+    hooks: Vec<ConfirmationHook>,
+    last_root: Mutex<Option<u64>>,
+}
+
+impl ConfirmationHooks { 
+    pub fn push(&mut self, hook: ConfirmationHook) { 
+        self.hooks.push(hook);
+    }
+
+    fn emit(&self, event: ConfirmationEvent) { 
+        for hook in self.hooks.iter() { 
+            hook(&event);
+        }
+    }
+}
+
+// resolves once `shutdown` is set, never when its sender is dropped without setting it
+async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) { 
+    while !*shutdown.borrow() { 
+        if shutdown.changed().await.is_err() { 
+            future::pending::<()>().await;
+        }
+    }
+}
+
+struct SlotSender { 
+    latest: watch::Sender<Option<u64>>,
+    // slots which were produced while the subscription was reconnecting
+    missed: mpsc::UnboundedSender<RangeInclusive<u64>>,
+}
+
+// returns false once nobody is listening for slots anymore
+async fn forward_slots(mut slots: impl Stream<Item = u64> + Unpin, last_seen: &mut Option<u64>, sender: &SlotSender) -> bool { 
+    let mut reconnected = last_seen.is_some();
+    while let Some(slot) = slots.next().await { 
+        if reconnected { 
+            reconnected = false;
+            if let Some(last) = *last_seen { 
+                if slot > last + 1 && sender.missed.send(last + 1..=slot - 1).is_err() { 
+                    return false;
+                }
+            }
+        }
+        *last_seen = Some(last_seen.map_or(slot, |last| last.max(slot)));
+        if sender.latest.send(Some(slot)).is_err() { 
+            return false;
+        }
+    }
+    true
+}
+
+// subscribes to new slots, reconnecting (and resubscribing) whenever the connection drops
+async fn subscribe_slots(ws_endpoint: String, sender: SlotSender) { 
+    let mut last_seen = None;
+    loop { 
+        let listening = match PubsubClient::new(&ws_endpoint).await { 
+            // a frozen bank is the first point its block can be verified, `slotSubscribe`
+            // is the fallback for nodes which dont support `slotsUpdatesSubscribe`
+            Ok(client) => match client.slot_updates_subscribe().await { 
+                Ok((updates, unsubscribe)) => { 
+                    let slots = updates.filter_map(|update| future::ready(match update { 
+                        SlotUpdate::Frozen { slot, .. } => Some(slot),
+                        _ => None,
+                    }));
+                    let listening = forward_slots(slots, &mut last_seen, &sender).await;
+                    unsubscribe().await;
+                    listening
+                }
+                Err(_) => match client.slot_subscribe().await { 
+                    Ok((slots, unsubscribe)) => { 
+                        let listening = forward_slots(slots.map(|slot_info| slot_info.slot), &mut last_seen, &sender).await;
+                        unsubscribe().await;
+                        listening
+                    }
+                    Err(e) => { 
+                        tracing::debug!("failed to subscribe to slots: {}", e);
+                        true
+                    }
+                },
+            },
+            Err(e) => { 
+                tracing::debug!("failed to connect to {}: {}", ws_endpoint, e);
+                true
+            }
+        };
+        if !listening { 
+            return;
+        }
+
+        tracing::debug!("slot subscription closed, reconnecting in {:?}", RECONNECT_DELAY);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// verification results of new slots from a slot subscription
+///
+/// only the latest slot is kept while a verification is running so a slow reader skips ahead
+/// instead of queueing, but slots missed while the subscription was reconnecting are backfilled
+pub struct SlotResults<'a, T: RpcTransport> { 
+    client: &'a LightClient<T>,
+    latest: watch::Receiver<Option<u64>>,
+    missed: mpsc::UnboundedReceiver<RangeInclusive<u64>>,
+    backfill: VecDeque<u64>,
+    next_latest: Option<u64>,
+    pub highest_verified: Option<u64>,
+    // set when resuming, the gap up to the first new slot is backfilled
+    resumed: bool,
+    shutdown: watch::Receiver<bool>,
+    subscription: JoinHandle<()>,
+}
+
+impl<'a, T: RpcTransport> SlotResults<'a, T> { 
+    /// stops the results once `shutdown` is set to true, a verification which already started still
+    /// returns its result first
+    pub fn until(mut self, shutdown: watch::Receiver<bool>) -> Self { 
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// the next (slot, result), None once the subscription is gone or on shutdown
+    pub async fn next(&mut self) -> Option<(u64, Result<VerificationResult, LightNodeError>)> { 
+        loop { 
+            if *self.shutdown.borrow() { 
+                return None;
+            }
+
+            while let Ok(missed) = self.missed.try_recv() { 
+                self.backfill.extend(missed);
+            }
+
+            // backfills go first since theyre older than the latest slot
+            let slot = if let Some(slot) = self.backfill.pop_front() { 
+                slot
+            } else if let Some(slot) = self.next_latest.take() { 
+                if self.highest_verified.map(|highest| slot <= highest).unwrap_or(false) { 
+                    continue;
+                }
+                if self.resumed { 
+                    self.resumed = false;
+                    if let Some(highest) = self.highest_verified { 
+                        self.backfill.extend(highest + 1..=slot);
+                        continue;
+                    }
+                }
+                slot
+            } else { 
+                tokio::select! { 
+                    // only errors when the subscription task is gone
+                    changed = self.latest.changed() => if changed.is_err() { 
+                        return None;
+                    },
+                    _ = shutdown_requested(&mut self.shutdown) => return None,
+                }
+                self.next_latest = *self.latest.borrow();
+                continue;
+            };
+
+            match self.client.verify_block(slot).await { 
+                Err(LightNodeError::SlotSkipped(_)) => continue,
+                result => { 
+                    if let Ok(result) = &result { 
+                        self.highest_verified = Some(self.highest_verified.map_or(slot, |highest| highest.max(slot)));
+                        self.client.notify_confirmations(result);
+                    }
+                    return Some((slot, result));
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T: RpcTransport> Drop for SlotResults<'a, T> { 
+    fn drop(&mut self) { 
+        self.subscription.abort();
+    }
+}
+
+impl<T: RpcTransport> LightClient<T> { 
+    // runs the confirmation hooks for a verified slot and for the root when it moved since the last call
+    fn notify_confirmations(&self, result: &VerificationResult) { 
+        let hooks = &self.confirmation_hooks;
+        if hooks.hooks.is_empty() { 
+            return;
+        }
+        if result.verified { 
+            hooks.emit(ConfirmationEvent { 
+                kind: ConfirmationKind::OptimisticallyConfirmed,
+                slot: result.slot,
+                confirmed_stake: result.voted_stake,
+                total_stake: result.total_stake,
+                timestamp: SystemTime::now(),
+            });
+        }
+
+        let root = self.root_tracker.lock().unwrap().as_ref()
+            .filter(|(_, tracker)| tracker.current_root() > 0)
+            .map(|(_, tracker)| (tracker.current_root(), tracker.rooted_stake(), tracker.total_stake()));
+        let (root, rooted_stake, total_stake) = match root { Some(x) => x, None => return };
+        { 
+            let mut last_root = hooks.last_root.lock().unwrap();
+            if last_root.map_or(false, |last| root <= last) { 
+                return;
+            }
+            *last_root = Some(root);
+        }
+        hooks.emit(ConfirmationEvent { 
+            kind: ConfirmationKind::Rooted,
+            slot: root,
+            confirmed_stake: rooted_stake,
+            total_stake,
+            timestamp: SystemTime::now(),
+        });
+    }
+
+    /// subscribes to new slots over the client's websocket endpoint, each one is verified
+    /// at the client's commitment (eg, finalized) when its read
+    pub fn slot_results(&self) -> SlotResults<'_, T> { 
+        self.slot_results_from(None)
+    }
+
+    /// `slot_results` resuming after `highest_verified` (eg, from a `WatchState`), the slots
+    /// between it and the first new slot are verified first
+    pub fn slot_results_from(&self, highest_verified: Option<u64>) -> SlotResults<'_, T> { 
+        let (latest, latest_receiver) = watch::channel(None);
+        let (missed, missed_receiver) = mpsc::unbounded_channel();
+        let subscription = tokio::spawn(subscribe_slots(self.ws_endpoint.clone(), SlotSender { latest, missed }));
+
+        SlotResults { 
+            client: self,
+            latest: latest_receiver,
+            missed: missed_receiver,
+            backfill: VecDeque::new(),
+            next_latest: None,
+            highest_verified,
+            resumed: highest_verified.is_some(),
+            // never set unless `until` replaces it
+            shutdown: watch::channel(false).1,
+            subscription,
+        }
+    }
+
+    /// calls `on_result` for every result of `slot_results`
+    pub async fn watch_slots<F>(&self, on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        self.watch_slots_from(None, on_result).await
+    }
+
+    /// `watch_slots` resuming after `highest_verified`
+    pub async fn watch_slots_from<F>(&self, highest_verified: Option<u64>, on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        self.watch_slots_until(highest_verified, watch::channel(false).1, on_result).await
+    }
+
+    /// `watch_slots_from` returning once `shutdown` is set (after the verification in flight)
+    pub async fn watch_slots_until<F>(&self, highest_verified: Option<u64>, shutdown: watch::Receiver<bool>, mut on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        let mut results = self.slot_results_from(highest_verified).until(shutdown);
+        while let Some((slot, result)) = results.next().await { 
+            on_result(slot, result);
+        }
+    }
+
+    /// verifies every new block from `blockSubscribe` (entries and votes), calling `on_result` once per slot
+    /// when the socket drops it reconnects with backoff and first catches up on the slots it missed
+    pub async fn watch_and_verify<F>(&self, on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        self.watch_and_verify_from(None, on_result).await
+    }
+
+    /// `watch_and_verify` resuming after `last_slot`, the blocks since are verified first
+    pub async fn watch_and_verify_from<F>(&self, last_slot: Option<u64>, on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        self.watch_and_verify_until(last_slot, watch::channel(false).1, on_result).await
+    }
+
+    /// `watch_and_verify_from` returning once `shutdown` is set (after the verification in flight),
+    /// the block subscription is closed first
+    pub async fn watch_and_verify_until<F>(&self, mut last_slot: Option<u64>, mut shutdown: watch::Receiver<bool>, mut on_result: F)
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        let mut failures = 0;
+        loop { 
+            match self.follow_blocks(&mut last_slot, &mut shutdown, &mut on_result).await { 
+                Ok(()) => { 
+                    failures = 0;
+                    tracing::debug!("block subscription closed, reconnecting");
+                }
+                Err(e) => { 
+                    failures += 1;
+                    tracing::debug!("block subscription failed ({} times in a row): {}", failures, e);
+                }
+            }
+            if *shutdown.borrow() { 
+                return;
+            }
+            tokio::select! { 
+                _ = tokio::time::sleep(self.retry_policy.delay_for_attempt(failures.max(1))) => {},
+                _ = shutdown_requested(&mut shutdown) => return,
+            }
+        }
+    }
+
+    /// `watch_and_verify_until` (with `blocks`) or `watch_slots_until` resuming from the `WatchState` in
+    /// `state_dir`, which is saved after every verified slot and once more (with the tracked votes) when
+    /// the watcher returns. an error saving the state after a slot is passed to `on_result` for that slot
+    pub async fn watch_with_state<F>(&self, blocks: bool, state_dir: &Path, shutdown: watch::Receiver<bool>, mut on_result: F) -> Result<WatchState, LightNodeError>
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        let mut state = WatchState::load(state_dir)?;
+        self.load_votes(state_dir)?;
+        let resume_from = state.highest_verified;
+        let on_result = |slot: u64, result: Result<VerificationResult, LightNodeError>| { 
+            let verified = matches!(&result, Ok(result) if result.verified);
+            on_result(slot, result);
+            if verified { 
+                state.update(slot, self.current_root());
+                if let Err(e) = state.save(state_dir) { 
+                    on_result(slot, Err(e));
+                }
+            }
+        };
+        if blocks { 
+            self.watch_and_verify_until(resume_from, shutdown, on_result).await;
+        } else { 
+            self.watch_slots_until(resume_from, shutdown, on_result).await;
+        }
+        state.save(state_dir)?;
+        self.save_votes(state_dir)?;
+        Ok(state)
+    }
+
+    // verifies the blocks until the subscription closes or on shutdown, `last_slot` is the last slot handled.
+    // a block which cant be fetched or verified ends it with the error, and is retried on the reconnect
+    async fn follow_blocks<F>(&self, last_slot: &mut Option<u64>, shutdown: &mut watch::Receiver<bool>, on_result: &mut F) -> Result<(), LightNodeError>
+    where 
+        F: FnMut(u64, Result<VerificationResult, LightNodeError>),
+    { 
+        let client = PubsubClient::new(&self.ws_endpoint).await
+            .map_err(|e| LightNodeError::Subscription(e.to_string()))?;
+        // only the slots are used, the blocks are refetched when theyre verified
+        let config = RpcBlockSubscribeConfig { 
+            commitment: Some(CommitmentConfig { commitment: self.commitment }),
+            encoding: Some(self.encoding),
+            transaction_details: Some(TransactionDetails::None),
+            show_rewards: Some(false),
+            max_supported_transaction_version: Some(0),
+        };
+        let (mut blocks, unsubscribe) = client.block_subscribe(RpcBlockSubscribeFilter::All, Some(config)).await
+            .map_err(|e| LightNodeError::Subscription(e.to_string()))?;
+
+        let mut error = None;
+        'blocks: loop { 
+            let update = tokio::select! { 
+                update = blocks.next() => match update { Some(update) => update, None => break },
+                _ = shutdown_requested(shutdown) => break,
+            };
+            let slot = update.value.slot;
+            // slots missed while reconnecting are verified first so none are skipped
+            let first_slot = match *last_slot { 
+                Some(last) if slot <= last => continue,
+                Some(last) => last + 1,
+                None => slot,
+            };
+            for slot in first_slot..=slot { 
+                // the rest of a backfill is picked up again on restart
+                if *shutdown.borrow() { 
+                    break;
+                }
+                match self.verify_block(slot).await { 
+                    Err(LightNodeError::SlotSkipped(_)) => {},
+                    Ok(result) => { 
+                        self.notify_confirmations(&result);
+                        on_result(slot, Ok(result))
+                    }
+                    // the slot isnt handled, its backfilled after the reconnect
+                    Err(e) => { 
+                        tracing::warn!(slot, "couldnt verify block, reconnecting: {}", e);
+                        error = Some(e);
+                        break 'blocks;
+                    }
+                }
+                *last_slot = Some(slot);
+            }
+        }
+
+        unsubscribe().await;
+        match error { Some(e) => Err(e), None => Ok(()) }
+    }
+}
@@ -0,0 +1,85 @@
+// the fixtures under tests/fixtures (see gen_fixtures.py): a synthetic slot signed with test keys,
+// its PoH entries and the rpc results the vote parsing needs
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use lightnode::{LightClient, LightNodeConfig, block_source::FileBlockSource, mock::{MockResponse, MockTransport}};
+use serde::Deserialize;
+use solana_sdk::hash::{Hash, hashv};
+use solana_transaction_status::{EntryProof, PartialEntry, UiConfirmedBlock};
+
+pub const FIXTURE_SLOT: u64 = 100;
+pub const FIXTURE_TICKS_PER_SLOT: u64 = 8;
+pub const FIXTURE_HASHES_PER_TICK: u64 = 100;
+
+pub fn fixtures_dir() -> PathBuf { 
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> T { 
+    let path = fixtures_dir().join(path);
+    let bytes = fs::read(&path).unwrap_or_else(|e| panic!("cant read {}: {}", path.display(), e));
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+// the tx encodings the fixture block is recorded with
+pub const FIXTURE_ENCODINGS: [&str; 3] = ["base64", "base58", "json"];
+
+// the base64 block is the default one, the others are under blocks/<encoding>
+fn blocks_dir(encoding: &str) -> String { 
+    match encoding { 
+        "base64" => "blocks".to_string(),
+        encoding => format!("blocks/{}", encoding),
+    }
+}
+
+pub fn fixture_block() -> UiConfirmedBlock { 
+    fixture_block_with_encoding("base64")
+}
+
+pub fn fixture_block_with_encoding(encoding: &str) -> UiConfirmedBlock { 
+    read_json(&format!("{}/{}.json", blocks_dir(encoding), FIXTURE_SLOT))
+}
+
+#[derive(Deserialize)]
+struct FixtureEntry { 
+    num_hashes: u64,
+    hash: String,
+    transaction_hash: Option<String>,
+}
+
+pub fn fixture_entries() -> Vec<EntryProof> { 
+    let entries: Vec<FixtureEntry> = read_json(&format!("blocks/{}.entries.json", FIXTURE_SLOT));
+    entries.into_iter()
+        .map(|x| EntryProof::PartialEntry(PartialEntry { 
+            num_hashes: x.num_hashes,
+            hash: Hash::from_str(&x.hash).unwrap(),
+            transaction_hash: x.transaction_hash.map(|hash| Hash::from_str(&hash).unwrap()),
+        }))
+        .collect()
+}
+
+/// the `result` of `method` from tests/fixtures/rpc
+pub fn fixture_result(method: &str) -> serde_json::Value { 
+    read_json(&format!("rpc/{}.json", method))
+}
+
+/// the bank hash the fixture votes vote for at `slot`
+pub fn fixture_bank_hash(slot: u64) -> Hash { 
+    hashv(&[b"lightnode fixture bank hash", &slot.to_le_bytes()])
+}
+
+/// a client reading the fixture block from disk and answering the rest from the fixture rpc results
+pub fn fixture_client() -> LightClient<MockTransport> { 
+    fixture_client_with_encoding("base64")
+}
+
+/// `fixture_client` reading the block recorded with `encoding`
+pub fn fixture_client_with_encoding(encoding: &str) -> LightClient<MockTransport> { 
+    let methods = ["getEpochInfo", "getEpochSchedule", "getVoteAccounts", "getMultipleAccounts", "getAccountInfo"];
+    let transport = methods.iter()
+        .fold(MockTransport::new(), |transport, method| transport.respond(method, MockResponse::Result(fixture_result(method))));
+    LightClient::with_transport(&LightNodeConfig::new("http://localhost:8899"), transport)
+        .with_block_source(FileBlockSource::new(fixtures_dir().join(blocks_dir(encoding))))
+}
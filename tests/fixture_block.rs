@@ -0,0 +1,78 @@
+mod common;
+
+use std::{collections::HashMap, str::FromStr};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use lightnode::{entries::{verify_entries, verify_entries_parallel, verify_ticks}, stake::{Threshold, stakes_from_vote_accounts}, vote::is_optimistically_confirmed};
+use solana_client::rpc_response::RpcVoteAccountStatus;
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature, vote::state::VoteState};
+use solana_transaction_status::EntryProof;
+
+use common::*;
+
+#[test]
+fn test_fixture_poh() { 
+    let block = fixture_block();
+    let previous_blockhash = Hash::from_str(&block.previous_blockhash).unwrap();
+    let blockhash = Hash::from_str(&block.blockhash).unwrap();
+    let entries = fixture_entries();
+
+    assert!(verify_entries(&previous_blockhash, &entries, &Signature::default()));
+    assert!(verify_entries_parallel(&previous_blockhash, &entries, &Signature::default()));
+    verify_ticks(&entries, FIXTURE_TICKS_PER_SLOT, Some(FIXTURE_HASHES_PER_TICK), &blockhash).unwrap();
+
+    // from any other start the path breaks
+    assert!(!verify_entries(&blockhash, &entries, &Signature::default()));
+    // and so does a tampered tx entry
+    let mut tampered = entries.clone();
+    if let EntryProof::PartialEntry(x) = &mut tampered[2] { 
+        x.transaction_hash = Some(Hash::new_unique());
+    }
+    assert!(!verify_entries(&previous_blockhash, &tampered, &Signature::default()));
+    assert!(!verify_entries_parallel(&previous_blockhash, &tampered, &Signature::default()));
+}
+
+#[test]
+fn test_fixture_vote_accounts() { 
+    // the vote states are written by hand, they have to read back like real vote accounts
+    let accounts = fixture_result("getMultipleAccounts")["value"].as_array().unwrap().clone();
+    assert_eq!(accounts.len(), 3);
+    for account in accounts { 
+        let data = BASE64.decode(account["data"][0].as_str().unwrap()).unwrap();
+        let vote_state = VoteState::deserialize(&data).unwrap();
+        assert_eq!(vote_state.last_voted_slot(), Some(99));
+        assert_eq!(vote_state.root_slot, Some(60));
+        assert_eq!(vote_state.authorized_voters().get_authorized_voter(0), Some(vote_state.node_pubkey));
+    }
+}
+
+#[tokio::test]
+async fn test_fixture_block_votes() { 
+    let vote_accounts: RpcVoteAccountStatus = serde_json::from_value(fixture_result("getVoteAccounts")).unwrap();
+    let stakes = stakes_from_vote_accounts(&vote_accounts).unwrap();
+
+    // the same block recorded with every tx encoding parses to the same votes
+    for encoding in FIXTURE_ENCODINGS { 
+        let client = fixture_client_with_encoding(encoding);
+        let votes = client.parse_block_votes(FIXTURE_SLOT, 1).await.unwrap();
+        // a compact vote state update, a tower sync after a compute budget ix and a plain vote, the transfer
+        // which only lists the vote program as an account isnt one and the vote which failed is skipped
+        assert_eq!(votes.len(), 3, "{}", encoding);
+        assert_eq!(client.metrics.votes_parsed.get(), 3);
+        assert_eq!(client.metrics.failed_votes.get(), 1);
+        let votes_by_account = votes.iter().map(|vote| (vote.vote_account, vote)).collect::<HashMap<Pubkey, _>>();
+        for (vote_account, stake_info) in stakes.iter() { 
+            // node 1s failed vote on 97 doesnt replace its vote on 99
+            let vote = votes_by_account[vote_account];
+            assert_eq!(vote.slot, 99);
+            assert_eq!(vote.bank_hash, fixture_bank_hash(99));
+            assert_eq!(vote.node_pubkey, Some(stake_info.node_pubkey));
+            assert_eq!(vote.stake, stake_info.activated_stake);
+            assert_eq!(vote.timestamp, Some(1_700_000_000));
+            assert!(vote.signature_verified && vote.voter_authorized && !vote.slot_hash_mismatch, "{}", encoding);
+            assert!(vote.counts());
+        }
+        assert!(client.take_suspicious_votes().is_empty());
+        assert!(is_optimistically_confirmed(&votes, &stakes, 99, Threshold::SUPERMAJORITY));
+    }
+}
@@ -0,0 +1,353 @@
+#!/usr/bin/env python3
+# regenerates the fixtures next to this file: a synthetic slot 100 signed with throwaway keys
+# (not a mainnet block), its PoH entries and the rpc results `parse_block_votes` needs for it
+#
+#   blocks/100.json          the `getBlock` result (base64 txs), read by `FileBlockSource`
+#   blocks/<encoding>/100.json  the same block with base58 and json txs
+#   blocks/100.entries.json  the block's entries as (num_hashes, hash, transaction_hash)
+#   rpc/<method>.json        the `result` of each rpc method
+#
+# usage: python3 tests/fixtures/gen_fixtures.py (needs `cryptography` for ed25519)
+import base64
+import hashlib
+import json
+import os
+import struct
+
+from cryptography.hazmat.primitives.asymmetric.ed25519 import Ed25519PrivateKey
+from cryptography.hazmat.primitives.serialization import Encoding, PublicFormat
+
+DIR = os.path.dirname(os.path.abspath(__file__))
+
+SLOT = 100
+TICKS_PER_SLOT = 8
+HASHES_PER_TICK = 100
+TIMESTAMP = 1_700_000_000
+# the tx encodings the block is written with
+ENCODINGS = ["base64", "base58", "json"]
+
+B58 = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+
+
+def b58encode(data):
+    n = int.from_bytes(data, "big")
+    out = ""
+    while n:
+        n, r = divmod(n, 58)
+        out = B58[r] + out
+    return "1" * (len(data) - len(data.lstrip(b"\0"))) + out
+
+
+def b58decode(s, size=32):
+    n = 0
+    for c in s:
+        n = n * 58 + B58.index(c)
+    return n.to_bytes(size, "big")
+
+
+def sha256(*parts):
+    return hashlib.sha256(b"".join(parts)).digest()
+
+
+class Keypair:
+    def __init__(self, seed):
+        self.key = Ed25519PrivateKey.from_private_bytes(bytes([seed]) * 32)
+        self.pubkey = self.key.public_key().public_bytes(Encoding.Raw, PublicFormat.Raw)
+
+    def sign(self, msg):
+        return self.key.sign(msg)
+
+
+VOTE_PROGRAM = b58decode("Vote111111111111111111111111111111111111111")
+SYSTEM_PROGRAM = bytes(32)
+COMPUTE_BUDGET_PROGRAM = b58decode("ComputeBudget111111111111111111111111111111")
+SLOT_HASHES_SYSVAR = b58decode("SysvarS1otHashes111111111111111111111111111")
+CLOCK_SYSVAR = b58decode("SysvarC1ock11111111111111111111111111111111")
+
+# `VoteError::VoteTooOld` as the custom error of a failed vote ix
+VOTE_TOO_OLD = 0
+
+
+def bank_hash(slot):
+    return sha256(b"lightnode fixture bank hash", struct.pack("<Q", slot))
+
+
+PREVIOUS_BLOCKHASH = sha256(b"lightnode fixture blockhash", struct.pack("<Q", SLOT - 1))
+
+
+def short_vec(n):
+    out = b""
+    while True:
+        byte = n & 0x7F
+        n >>= 7
+        if n:
+            out += bytes([byte | 0x80])
+        else:
+            return out + bytes([byte])
+
+
+varint = short_vec
+
+
+def option_i64(x):
+    return b"\0" if x is None else b"\1" + struct.pack("<q", x)
+
+
+# (slot, confirmation count) lockouts in the compact layout, offsets from the root
+def compact_tower(root, lockouts, hash, timestamp):
+    out = struct.pack("<Q", root) + short_vec(len(lockouts))
+    prev = root
+    for slot, confirmation_count in lockouts:
+        out += varint(slot - prev) + bytes([confirmation_count])
+        prev = slot
+    return out + hash + option_i64(timestamp)
+
+
+def vote_ix_data(slots, hash, timestamp):
+    data = struct.pack("<I", 2) + struct.pack("<Q", len(slots))
+    data += b"".join(struct.pack("<Q", slot) for slot in slots)
+    return data + hash + option_i64(timestamp)
+
+
+def compact_update_vote_state_data(root, lockouts, hash, timestamp):
+    return struct.pack("<I", 12) + compact_tower(root, lockouts, hash, timestamp)
+
+
+def tower_sync_data(root, lockouts, hash, timestamp, block_id):
+    return struct.pack("<I", 14) + compact_tower(root, lockouts, hash, timestamp) + block_id
+
+
+# a legacy message, `ixs` are (program id index, account indexes, data)
+def message(num_signed, num_readonly_signed, num_readonly_unsigned, account_keys, ixs):
+    out = bytes([num_signed, num_readonly_signed, num_readonly_unsigned])
+    out += short_vec(len(account_keys)) + b"".join(account_keys)
+    out += PREVIOUS_BLOCKHASH
+    out += short_vec(len(ixs))
+    for program_id_index, accounts, data in ixs:
+        out += bytes([program_id_index]) + short_vec(len(accounts)) + bytes(accounts)
+        out += short_vec(len(data)) + data
+    return out
+
+
+# the `json` encoding of the same message
+def ui_message(num_signed, num_readonly_signed, num_readonly_unsigned, account_keys, ixs):
+    return {
+        "header": {
+            "numRequiredSignatures": num_signed,
+            "numReadonlySignedAccounts": num_readonly_signed,
+            "numReadonlyUnsignedAccounts": num_readonly_unsigned,
+        },
+        "accountKeys": [b58encode(key) for key in account_keys],
+        "recentBlockhash": b58encode(PREVIOUS_BLOCKHASH),
+        "instructions": [
+            {"programIdIndex": program_id_index, "accounts": accounts, "data": b58encode(data), "stackHeight": None}
+            for program_id_index, accounts, data in ixs
+        ],
+    }
+
+
+# (signatures, serialized tx, json tx) of the message built from `args`
+def transaction(signers, *args):
+    msg = message(*args)
+    signatures = [signer.sign(msg) for signer in signers]
+    ui_transaction = {"signatures": [b58encode(signature) for signature in signatures], "message": ui_message(*args)}
+    return signatures, short_vec(len(signatures)) + b"".join(signatures) + msg, ui_transaction
+
+
+def merkle_root(signatures):
+    level = [sha256(b"\0", signature) for signature in signatures]
+    while len(level) > 1:
+        level = [sha256(b"\1", level[i], level[min(i + 1, len(level) - 1)]) for i in range(0, len(level), 2)]
+    return level[0]
+
+
+def poh(hash, num_hashes, mixin):
+    for _ in range(num_hashes - (mixin is not None)):
+        hash = sha256(hash)
+    return hash if mixin is None else sha256(hash, mixin)
+
+
+# the vote state (`VoteStateVersions::Current`) of a vote account
+def vote_state(node, authorized_voter, root, lockouts):
+    out = struct.pack("<I", 2) + node + node + bytes([10])
+    out += struct.pack("<Q", len(lockouts))
+    for slot, confirmation_count in lockouts:
+        out += b"\0" + struct.pack("<QI", slot, confirmation_count)
+    out += b"\1" + struct.pack("<Q", root)
+    out += struct.pack("<Q", 1) + struct.pack("<Q", 0) + authorized_voter
+    out += (bytes(32) + struct.pack("<QQ", 0, 0)) * 32 + struct.pack("<Q", 31) + b"\1"
+    out += struct.pack("<Q", 0)
+    out += struct.pack("<Qq", lockouts[-1][0], TIMESTAMP)
+    # vote accounts are allocated at their full size
+    return out.ljust(3762, b"\0")
+
+
+def ui_account(data, owner, lamports=1_000_000_000):
+    return {
+        "lamports": lamports,
+        "data": [base64.b64encode(data).decode(), "base64"],
+        "owner": b58encode(owner),
+        "executable": False,
+        "rentEpoch": 0,
+        "space": len(data),
+    }
+
+
+# the tx as `getBlock` returns it with `encoding`
+def encoded_transaction(tx, encoding):
+    _, tx, ui_transaction = tx
+    if encoding == "base64":
+        return [base64.b64encode(tx).decode(), "base64"]
+    if encoding == "base58":
+        return [b58encode(tx), "base58"]
+    return ui_transaction
+
+
+# `err` is the `TransactionError` of a tx which landed but failed
+def tx_with_meta(tx, encoding, err=None):
+    return {
+        "transaction": encoded_transaction(tx, encoding),
+        "meta": {
+            "err": err,
+            "status": {"Ok": None} if err is None else {"Err": err},
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "innerInstructions": [],
+            "logMessages": [],
+            "preTokenBalances": [],
+            "postTokenBalances": [],
+            "rewards": [],
+            "loadedAddresses": {"writable": [], "readonly": []},
+            "computeUnitsConsumed": 2100,
+        },
+    }
+
+
+def main():
+    # (node identity, vote account, stake), the node is the authorized voter of its vote account
+    validators = [(Keypair(1), Keypair(2), 600), (Keypair(3), Keypair(4), 300), (Keypair(5), Keypair(6), 100)]
+    tower = [(97, 3), (98, 2), (99, 1)]
+    root = 60
+
+    (node1, vote1, _), (node2, vote2, _), (node3, vote3, _) = validators
+    payer = Keypair(7)
+
+    txs = []
+    # a `CompactUpdateVoteState`: vote account, authorized voter
+    txs.append(transaction([node1], 1, 0, 1, [node1.pubkey, vote1.pubkey, VOTE_PROGRAM], [
+        (2, [1, 0], compact_update_vote_state_data(root, tower, bank_hash(99), TIMESTAMP)),
+    ]))
+    # a `TowerSync` after a compute budget ix (`SetComputeUnitLimit`)
+    txs.append(transaction([node2], 1, 0, 2, [node2.pubkey, vote2.pubkey, COMPUTE_BUDGET_PROGRAM, VOTE_PROGRAM], [
+        (2, [], bytes([2]) + struct.pack("<I", 2100)),
+        (3, [1, 0], tower_sync_data(root, tower, bank_hash(99), TIMESTAMP, sha256(b"block id 99"))),
+    ]))
+    # a plain `Vote`: vote account, slot hashes sysvar, clock sysvar, authorized voter
+    txs.append(transaction([node3], 1, 0, 3, [node3.pubkey, vote3.pubkey, SLOT_HASHES_SYSVAR, CLOCK_SYSVAR, VOTE_PROGRAM], [
+        (4, [1, 2, 3, 0], vote_ix_data([98, 99], bank_hash(99), TIMESTAMP)),
+    ]))
+    # a transfer which only lists the vote program as an account, it isnt a vote
+    txs.append(transaction([payer], 1, 0, 2, [payer.pubkey, vote1.pubkey, SYSTEM_PROGRAM, VOTE_PROGRAM], [
+        (2, [0, 1, 3], struct.pack("<IQ", 2, 1_000_000)),
+    ]))
+    # a vote from node 1 on a slot its tower already passed, it lands but fails with `VoteTooOld`
+    txs.append(transaction([node1], 1, 0, 3, [node1.pubkey, vote1.pubkey, SLOT_HASHES_SYSVAR, CLOCK_SYSVAR, VOTE_PROGRAM], [
+        (4, [1, 2, 3, 0], vote_ix_data([97], bank_hash(97), TIMESTAMP)),
+    ]))
+    errors = {4: {"InstructionError": [0, {"Custom": VOTE_TOO_OLD}]}}
+
+    # tx entries (tick index, hashes before the entry, txs) and a tick closing each of the ticks
+    tx_entries = [(0, 17, [0]), (1, 40, [1]), (3, 5, [2, 3]), (5, 30, [4])]
+    entries = []
+    hash = PREVIOUS_BLOCKHASH
+    for tick in range(TICKS_PER_SLOT):
+        hashes_since_tick = 0
+        for _, num_hashes, tx_indexes in [x for x in tx_entries if x[0] == tick]:
+            transaction_hash = merkle_root([txs[i][0][0] for i in tx_indexes])
+            hash = poh(hash, num_hashes, transaction_hash)
+            hashes_since_tick += num_hashes
+            entries.append({"num_hashes": num_hashes, "hash": b58encode(hash), "transaction_hash": b58encode(transaction_hash)})
+        num_hashes = HASHES_PER_TICK - hashes_since_tick
+        hash = poh(hash, num_hashes, None)
+        entries.append({"num_hashes": num_hashes, "hash": b58encode(hash), "transaction_hash": None})
+    blockhash = hash
+
+    # the same block with each tx encoding
+    blocks = {
+        encoding: {
+            "previousBlockhash": b58encode(PREVIOUS_BLOCKHASH),
+            "blockhash": b58encode(blockhash),
+            "parentSlot": SLOT - 1,
+            "transactions": [tx_with_meta(tx, encoding, errors.get(i)) for i, tx in enumerate(txs)],
+            "blockTime": TIMESTAMP,
+            "blockHeight": SLOT - 4,
+        }
+        for encoding in ENCODINGS
+    }
+
+    vote_accounts = sorted(validators, key=lambda x: x[1].pubkey)
+    rpc = {
+        "getEpochInfo": {
+            "absoluteSlot": SLOT + 4,
+            "blockHeight": SLOT,
+            "epoch": 0,
+            "slotIndex": SLOT + 4,
+            "slotsInEpoch": 432000,
+            "transactionCount": None,
+        },
+        "getEpochSchedule": {
+            "slotsPerEpoch": 432000,
+            "leaderScheduleSlotOffset": 432000,
+            "warmup": False,
+            "firstNormalEpoch": 0,
+            "firstNormalSlot": 0,
+        },
+        "getVoteAccounts": {
+            "current": [
+                {
+                    "votePubkey": b58encode(vote.pubkey),
+                    "nodePubkey": b58encode(node.pubkey),
+                    "activatedStake": stake,
+                    "epochVoteAccount": True,
+                    "commission": 10,
+                    "lastVote": 99,
+                    "epochCredits": [],
+                    "rootSlot": root,
+                }
+                for node, vote, stake in validators
+            ],
+            "delinquent": [],
+        },
+        # the mock answers every call of a method the same way, this is the one for the
+        # vote accounts (sorted like `authorized_voters` requests them)
+        "getMultipleAccounts": {
+            "context": {"slot": SLOT + 4},
+            "value": [ui_account(vote_state(node.pubkey, node.pubkey, root, tower), VOTE_PROGRAM) for node, _, _ in vote_accounts],
+        },
+        # and the `SlotHashes` sysvar for this one, newest first
+        "getAccountInfo": {
+            "context": {"slot": SLOT + 4},
+            "value": ui_account(
+                struct.pack("<Q", 50) + b"".join(struct.pack("<Q", slot) + bank_hash(slot) for slot in range(SLOT - 1, SLOT - 51, -1)),
+                b58decode("Sysvar1111111111111111111111111111111111111"),
+            ),
+        },
+    }
+
+    for encoding, block in blocks.items():
+        # the base64 block is the default one, next to the entries
+        blocks_dir = os.path.join(DIR, "blocks") if encoding == "base64" else os.path.join(DIR, "blocks", encoding)
+        os.makedirs(blocks_dir, exist_ok=True)
+        with open(os.path.join(blocks_dir, f"{SLOT}.json"), "w") as f:
+            json.dump(block, f, indent=2)
+    os.makedirs(os.path.join(DIR, "rpc"), exist_ok=True)
+    with open(os.path.join(DIR, "blocks", f"{SLOT}.entries.json"), "w") as f:
+        json.dump(entries, f, indent=2)
+    for method, result in rpc.items():
+        with open(os.path.join(DIR, "rpc", f"{method}.json"), "w") as f:
+            json.dump(result, f, indent=2)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use lightnode::{
+    LightClient, LightNodeConfig, LightNodeError,
+    failover::FailoverTransport,
+    mock::{MockResponse, MockTransport},
+    retry::RetryPolicy,
+    transport::{RpcTransport, TransportError},
+};
+use serde_json::{Value, json};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
+
+fn config(max_attempts: u32) -> LightNodeConfig { 
+    let mut config = LightNodeConfig::new("http://localhost:8899");
+    config.retry_policy = RetryPolicy { max_attempts, base_delay: Duration::from_millis(10), max_delay: Duration::from_millis(50) };
+    config.request_timeout = REQUEST_TIMEOUT;
+    config
+}
+
+fn mock_client(transport: MockTransport, max_attempts: u32) -> LightClient<MockTransport> { 
+    LightClient::with_transport(&config(max_attempts), transport)
+}
+
+#[tokio::test]
+async fn test_retries_until_success() { 
+    let transport = MockTransport::new()
+        .respond("getSlot", MockResponse::Status(503))
+        .respond("getSlot", MockResponse::Status(503))
+        .respond("getSlot", MockResponse::Result(json!(42)));
+    let client = mock_client(transport, 3);
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    assert_eq!(client.transport.num_requests("getSlot"), 3);
+
+    // one failure more than the budget
+    let transport = MockTransport::new()
+        .respond("getSlot", MockResponse::Status(503))
+        .respond("getSlot", MockResponse::Status(503))
+        .respond("getSlot", MockResponse::Status(503))
+        .respond("getSlot", MockResponse::Result(json!(42)));
+    let client = mock_client(transport, 3);
+    match client.get_slot().await { 
+        Err(LightNodeError::RetriesExhausted { attempts: 3, source: TransportError::Status(503) }) => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(client.transport.num_requests("getSlot"), 3);
+}
+
+#[tokio::test]
+async fn test_rate_limited_backoff() { 
+    let retry_after = Duration::from_millis(300);
+    let transport = MockTransport::new()
+        .respond("getSlot", MockResponse::RateLimited(Some(retry_after)))
+        .respond("getSlot", MockResponse::Result(json!(42)));
+    let client = mock_client(transport, 3);
+
+    let start = Instant::now();
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    // the retry waits out the endpoint's retry-after
+    assert!(start.elapsed() >= retry_after, "retried after {:?}", start.elapsed());
+    assert_eq!(client.transport.num_requests("getSlot"), 2);
+}
+
+#[tokio::test]
+async fn test_hang_times_out() { 
+    let client = mock_client(MockTransport::new().respond("getSlot", MockResponse::Hang), 1);
+    match client.get_slot().await { 
+        Err(LightNodeError::Transport(TransportError::Timeout(timeout))) => assert_eq!(timeout, REQUEST_TIMEOUT),
+        r => panic!("unexpected {:?}", r),
+    }
+
+    // every attempt times out on its own
+    let client = mock_client(MockTransport::new().respond("getSlot", MockResponse::Hang), 2);
+    match client.get_slot().await { 
+        Err(LightNodeError::RetriesExhausted { attempts: 2, source: TransportError::Timeout(_) }) => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(client.transport.num_requests("getSlot"), 2);
+}
+
+#[tokio::test]
+async fn test_rpc_error_object() { 
+    let transport = MockTransport::new()
+        .respond("getSlot", MockResponse::Error { code: -32005, message: "node is behind".to_string() });
+    let client = mock_client(transport, 3);
+    match client.get_slot().await { 
+        Err(LightNodeError::Rpc { code, message }) => assert_eq!((code, message.as_str()), (-32005, "node is behind")),
+        r => panic!("unexpected {:?}", r),
+    }
+    // the rpc answered so its not retried
+    assert_eq!(client.transport.num_requests("getSlot"), 1);
+}
+
+// answers every request with the id of the one after it
+struct WrongIdTransport;
+
+#[async_trait]
+impl RpcTransport for WrongIdTransport { 
+    async fn send(&self, body: String) -> Result<String, TransportError> { 
+        let request: Value = serde_json::from_str(&body).unwrap();
+        let id = request["id"].as_u64().unwrap() + 1;
+        Ok(json!({ "jsonrpc": "2.0", "id": id, "result": 42 }).to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_mismatched_id() { 
+    let client = LightClient::with_transport(&config(3), WrongIdTransport);
+    match client.get_slot().await { 
+        Err(LightNodeError::InvalidResponse(_)) => {}
+        r => panic!("unexpected {:?}", r),
+    }
+}
+
+#[tokio::test]
+async fn test_failover_to_second_endpoint() { 
+    let transports = vec![
+        MockTransport::new().respond("getSlot", MockResponse::Status(503)),
+        MockTransport::new().respond("getSlot", MockResponse::Result(json!(42))),
+    ];
+    let transport = FailoverTransport::with_transports(transports, Duration::from_secs(60));
+    let client = LightClient::with_transport(&config(1), transport);
+
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    assert_eq!(client.transport.transports()[0].num_requests("getSlot"), 1);
+    assert_eq!(client.transport.transports()[1].num_requests("getSlot"), 1);
+
+    // the primary is skipped until its due a health check
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    assert_eq!(client.transport.transports()[0].num_requests("getSlot"), 1);
+    assert_eq!(client.transport.transports()[1].num_requests("getSlot"), 2);
+}
+
+#[tokio::test]
+async fn test_failover_from_rate_limited_endpoint() { 
+    let retry_after = Duration::from_secs(5);
+    let transports = vec![
+        MockTransport::new().respond("getSlot", MockResponse::RateLimited(Some(retry_after))),
+        MockTransport::new().respond("getSlot", MockResponse::Result(json!(42))),
+    ];
+    let transport = FailoverTransport::with_transports(transports, Duration::from_secs(60));
+    let client = LightClient::with_transport(&config(3), transport);
+
+    // the 429 moves the request to the second endpoint right away, the primary's retry-after doesnt
+    // put the whole client in a cooldown
+    let start = Instant::now();
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    assert_eq!(client.get_slot().await.unwrap(), 42);
+    assert!(start.elapsed() < retry_after / 10, "failed over after {:?}", start.elapsed());
+    assert_eq!(client.transport.transports()[0].num_requests("getSlot"), 1);
+    assert_eq!(client.transport.transports()[1].num_requests("getSlot"), 2);
+}
+
+#[tokio::test]
+async fn test_unconfirmed_tx_times_out() { 
+    let mut config = config(1);
+    config.block_wait_timeout = Duration::from_millis(100);
+    // the rpc never has the tx
+    let transport = MockTransport::new().respond("getTransaction", MockResponse::Result(Value::Null));
+    let client = LightClient::with_transport(&config, transport);
+    match client.get_tx(Default::default()).await { 
+        Err(LightNodeError::Timeout(timeout)) => assert_eq!(timeout, Duration::from_millis(100)),
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(client.transport.num_requests("getTransaction"), 2);
+}
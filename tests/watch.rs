@@ -0,0 +1,34 @@
+use std::{fs, time::Duration};
+
+use lightnode::{LightClient, LightNodeConfig, mock::MockTransport, state::{STATE_FILE, WatchState}, vote_tracker::VOTES_FILE};
+
+#[tokio::test]
+async fn test_shutdown_flushes_state() { 
+    let mut config = LightNodeConfig::new("http://localhost:8899");
+    // nothing listens here so the subscriptions keep reconnecting until the shutdown
+    config.ws_endpoint = "ws://127.0.0.1:1".to_string();
+    let client = LightClient::with_transport(&config, MockTransport::new());
+
+    for blocks in [false, true] { 
+        let dir = std::env::temp_dir().join(format!("lightnode-watch-{}-{}", std::process::id(), blocks));
+        let _ = fs::remove_dir_all(&dir);
+        let saved = WatchState { highest_verified: Some(41), root: Some(10) };
+        saved.save(&dir).unwrap();
+
+        let (shutdown_sender, shutdown) = tokio::sync::watch::channel(false);
+        let watcher = client.watch_with_state(blocks, &dir, shutdown, |slot, result| panic!("unexpected slot {}: {:?}", slot, result));
+        let shutdown = async { 
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            shutdown_sender.send(true).unwrap();
+        };
+        let (state, _) = tokio::time::timeout(Duration::from_secs(5), async { tokio::join!(watcher, shutdown) }).await
+            .expect("the watcher didnt shut down");
+
+        // the resumed state and the (empty) tracked votes are written on the way out
+        assert_eq!(state.unwrap(), saved);
+        assert_eq!(WatchState::load(&dir).unwrap(), saved);
+        assert_eq!(fs::read_to_string(dir.join(VOTES_FILE)).unwrap(), "[]");
+        assert!(!dir.join(format!("{}.tmp", STATE_FILE)).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}